@@ -0,0 +1,183 @@
+//! Opening book
+//!
+//! This module reads opening books in the [Polyglot] format.
+//!
+//! [Polyglot]: http://hgm.nubati.net/book_format.html
+
+use std::{error::Error, fmt};
+
+use crate::{ChessMove, File, Game, LanMove, Piece, Promotion, Square};
+
+/// An error that originated from [`Book`] parsing.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ParseBookError {
+    /// The size of the book is not a multiple of [`Book::ENTRY_SIZE`].
+    BadLength,
+    /// An entry contains a move that can not be decoded.
+    BadMove,
+}
+
+impl fmt::Display for ParseBookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl Error for ParseBookError {}
+
+/// A single entry of the [`Book`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BookEntry {
+    /// [`Game::polyglot_key`] of the position.
+    pub key: u64,
+    /// The suggested move.
+    ///
+    /// Castling is encoded as the king capturing its own rook,
+    /// just like in the book file.
+    pub lan_move: LanMove,
+    /// How often the move should be played relative to the other
+    /// moves in the same position. Zero means never.
+    pub weight: u16,
+    /// Learning data, unused by the engine.
+    pub learn: u32,
+}
+
+/// A [Polyglot] opening book.
+///
+/// [Polyglot]: http://hgm.nubati.net/book_format.html
+#[derive(Debug, Clone, Default)]
+pub struct Book {
+    /// Entries sorted by key.
+    entries: Vec<BookEntry>,
+}
+
+impl Book {
+    /// Size of a single [`BookEntry`] within the book file in bytes.
+    pub const ENTRY_SIZE: usize = 16;
+
+    /// Parses a book from the contents of a `.bin` file.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ParseBookError> {
+        if !bytes.len().is_multiple_of(Self::ENTRY_SIZE) {
+            return Err(ParseBookError::BadLength);
+        }
+
+        let mut entries = bytes
+            .chunks_exact(Self::ENTRY_SIZE)
+            .map(|chunk| {
+                let key = u64::from_be_bytes(chunk[0..8].try_into().unwrap());
+                let packed = u16::from_be_bytes(chunk[8..10].try_into().unwrap());
+                let weight = u16::from_be_bytes(chunk[10..12].try_into().unwrap());
+                let learn = u32::from_be_bytes(chunk[12..16].try_into().unwrap());
+                let lan_move = decode_move(packed).ok_or(ParseBookError::BadMove)?;
+                Ok(BookEntry {
+                    key,
+                    lan_move,
+                    weight,
+                    learn,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        // Books are supposed to be sorted already, but the binary
+        // search relies on it, so better safe than sorry.
+        entries.sort_by_key(|entry| entry.key);
+        Ok(Self { entries })
+    }
+    /// Returns the number of entries in the book.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    /// Returns `true` if the book has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    /// Returns all entries with the specified key.
+    pub fn entries(&self, key: u64) -> &[BookEntry] {
+        let start = self.entries.partition_point(|entry| entry.key < key);
+        let len = self.entries[start..].partition_point(|entry| entry.key == key);
+        &self.entries[start..start + len]
+    }
+    /// Returns the legal book moves for the position with their weights.
+    ///
+    /// Moves with zero weight are skipped.
+    pub fn moves(&self, game: &Game) -> Vec<(ChessMove, u16)> {
+        let mut game = game.clone();
+        let mut moves = Vec::new();
+        for entry in self.entries(game.polyglot_key()) {
+            if entry.weight == 0 {
+                continue;
+            }
+            let lan_move = decode_castling(&game, entry.lan_move);
+            let Some(chess_move) = game.lan_move(lan_move) else {
+                continue;
+            };
+            if game.walk().map_move_if_legal(chess_move, |_| ()) {
+                moves.push((chess_move, entry.weight));
+            }
+        }
+        moves
+    }
+    /// Picks a book move for the position at random, weighted by
+    /// [`BookEntry::weight`]. Returns `None` if the position is out of book.
+    ///
+    /// `random` is expected to be uniformly distributed.
+    pub fn pick_move(&self, game: &Game, random: u64) -> Option<ChessMove> {
+        let moves = self.moves(game);
+        let total: u64 = moves.iter().map(|&(_, weight)| weight as u64).sum();
+        if total == 0 {
+            return None;
+        }
+
+        let mut point = random % total;
+        for (chess_move, weight) in moves {
+            if point < weight as u64 {
+                return Some(chess_move);
+            }
+            point -= weight as u64;
+        }
+        unreachable!()
+    }
+}
+
+/// Decodes a move from the Polyglot format.
+fn decode_move(packed: u16) -> Option<LanMove> {
+    let square = |bits: u16| {
+        let file = bits & 0b111;
+        let rank = (bits >> 3) & 0b111;
+        Square::from_repr((rank * 8 + file) as u8)
+    };
+    let to = square(packed)?;
+    let from = square(packed >> 6)?;
+    let promotion = match (packed >> 12) & 0b111 {
+        0 => None,
+        1 => Some(Promotion::Knight),
+        2 => Some(Promotion::Bishop),
+        3 => Some(Promotion::Rook),
+        4 => Some(Promotion::Queen),
+        _ => return None,
+    };
+    Some(LanMove {
+        from,
+        to,
+        promotion,
+    })
+}
+
+/// Converts the castling from the "king takes rook" form
+/// into the regular one.
+fn decode_castling(game: &Game, lan_move: LanMove) -> LanMove {
+    let board = game.board();
+    let is_king = board.get_piece_at(lan_move.from) == Some(Piece::King);
+    let is_own_rook = board.get_piece_at(lan_move.to) == Some(Piece::Rook)
+        && board.get_color_at(lan_move.to) == board.get_color_at(lan_move.from);
+    if !is_king || !is_own_rook || lan_move.from.file() != File::E {
+        return lan_move;
+    }
+
+    let rank = lan_move.from.rank();
+    let to = match lan_move.to.file() {
+        File::H => Square::new(rank, File::G),
+        File::A => Square::new(rank, File::C),
+        _ => return lan_move,
+    };
+    LanMove { to, ..lan_move }
+}
@@ -0,0 +1,254 @@
+//! Static evaluation
+//!
+//! This module provides the [`Evaluator`] trait used by the search
+//! to score the quiet positions and its default implementation.
+
+use std::{
+    fmt,
+    ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign},
+};
+
+use strum::IntoEnumIterator;
+
+use crate::{eval::pawns::PawnTable, Color, Game, Piece, Score};
+
+pub use params::{Coefficients, EvalScore, Params, PARAMS, SAFETY_SIZE};
+pub use trace::{Term, Trace};
+
+pub(crate) use pst::{material_value, phase_weight, square_score};
+
+mod king;
+mod mobility;
+#[cfg(feature = "nnue")]
+pub mod nnue;
+mod params;
+mod pawns;
+mod pst;
+mod trace;
+
+/// Game phase of the initial position.
+///
+/// The phase is the sum of [`Game::phase`] weights of all pieces
+/// (minor pieces count 1, rooks 2 and queens 4), so it drops
+/// towards zero as the pieces are exchanged.
+pub const MAX_PHASE: i32 = 24;
+
+/// A pair of middlegame and endgame scores in centi-pawns.
+///
+/// The final score is interpolated between the two based on the game phase.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct TaperedScore {
+    /// Score in the middlegame.
+    pub mg: i32,
+    /// Score in the endgame.
+    pub eg: i32,
+}
+
+impl TaperedScore {
+    /// Score that is zero in all phases.
+    pub const ZERO: Self = Self::new(0, 0);
+
+    /// Creates a new [`TaperedScore`].
+    pub const fn new(mg: i32, eg: i32) -> Self {
+        Self { mg, eg }
+    }
+    /// Interpolates the score for the game phase.
+    ///
+    /// Phases larger than [`MAX_PHASE`] (possible after promotions)
+    /// are treated as the middlegame.
+    pub fn taper(self, phase: i32) -> i32 {
+        let phase = phase.clamp(0, MAX_PHASE);
+        (self.mg * phase + self.eg * (MAX_PHASE - phase)) / MAX_PHASE
+    }
+}
+
+impl Add for TaperedScore {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.mg + rhs.mg, self.eg + rhs.eg)
+    }
+}
+
+impl AddAssign for TaperedScore {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for TaperedScore {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.mg - rhs.mg, self.eg - rhs.eg)
+    }
+}
+
+impl SubAssign for TaperedScore {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl Neg for TaperedScore {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.mg, -self.eg)
+    }
+}
+
+impl Mul<i32> for TaperedScore {
+    type Output = Self;
+
+    fn mul(self, rhs: i32) -> Self {
+        Self::new(self.mg * rhs, self.eg * rhs)
+    }
+}
+
+impl Div<i32> for TaperedScore {
+    type Output = Self;
+
+    fn div(self, rhs: i32) -> Self {
+        Self::new(self.mg / rhs, self.eg / rhs)
+    }
+}
+
+/// Static evaluation used by the search.
+///
+/// Every search thread owns its evaluator, so the implementations
+/// are free to keep caches between the calls.
+pub trait Evaluator: fmt::Debug + Send {
+    /// Returns the static evaluation of the position for the player who is about to make a turn.
+    fn evaluate(&mut self, game: &Game) -> Score;
+    /// Returns the breakdown of the static evaluation into its terms.
+    ///
+    /// This is meant for debugging and can be slow, but
+    /// [`Trace::score`] must match [`Self::evaluate`].
+    fn trace(&mut self, game: &Game) -> Trace;
+}
+
+/// Creates a new instance of an [`Evaluator`] for a search thread.
+///
+/// See [`ServerCommand::SetEvaluator`](crate::ServerCommand::SetEvaluator).
+pub type EvaluatorFactory = fn() -> Box<dyn Evaluator>;
+
+/// The default [`Evaluator`].
+///
+/// Sums the material, [piece-square tables], pawn structure, mobility
+/// and king safety terms for the middlegame and the endgame, then [tapers]
+/// the result by the game phase. Material and piece-square tables are kept up to date
+/// incrementally by the [`Game`], pawn structure is cached in a pawn
+/// hash table.
+///
+/// [piece-square tables]: https://www.chessprogramming.org/Piece-Square_Tables
+/// [tapers]: https://www.chessprogramming.org/Tapered_Eval
+#[derive(Debug, Clone, Default)]
+pub struct TaperedEvaluator {
+    pawns: PawnTable,
+}
+
+impl TaperedEvaluator {
+    /// Returns a boxed [`TaperedEvaluator`], this is an [`EvaluatorFactory`].
+    pub fn boxed() -> Box<dyn Evaluator> {
+        Box::<Self>::default()
+    }
+}
+
+impl Evaluator for TaperedEvaluator {
+    fn evaluate(&mut self, game: &Game) -> Score {
+        let pawns = self.pawns.probe(game);
+        let board = game.board();
+        let mut total = game.psq();
+        for color in Color::iter() {
+            let passed = pawns.passed[color as usize];
+            let score = pawns.score[color as usize]
+                + pawns::evaluate_passed_paths(board, color, passed, &PARAMS)
+                + mobility::evaluate_mobility(board, color, &PARAMS)
+                + king::evaluate_king_safety(board, color, &PARAMS);
+            match color {
+                Color::White => total += score,
+                Color::Black => total -= score,
+            }
+        }
+        match game.turn() {
+            Color::White => total += PARAMS.tempo,
+            Color::Black => total -= PARAMS.tempo,
+        }
+        let score = total.taper(game.phase());
+        match game.turn() {
+            Color::White => Score::Cp(score),
+            Color::Black => Score::Cp(-score),
+        }
+    }
+    fn trace(&mut self, game: &Game) -> Trace {
+        let pawns = self.pawns.probe(game);
+        let board = game.board();
+        let mut trace = Trace::new(game.phase(), game.turn());
+        for color in Color::iter() {
+            let mut material = TaperedScore::ZERO;
+            let mut psq = TaperedScore::ZERO;
+            for piece in Piece::iter() {
+                for sq in board.get_color_piece(color, piece) {
+                    material += material_value(piece);
+                    psq += square_score(color, piece, sq);
+                }
+            }
+            // Square scores are from the white player's point of view.
+            if color == Color::Black {
+                psq = -psq;
+            }
+            let passed = pawns.passed[color as usize];
+            let passed_paths = pawns::evaluate_passed_paths(board, color, passed, &PARAMS);
+            trace.set(Term::Material, color, material);
+            trace.set(Term::Pst, color, psq - material);
+            trace.set(
+                Term::Pawns,
+                color,
+                pawns.score[color as usize] + passed_paths,
+            );
+            trace.set(
+                Term::KingSafety,
+                color,
+                king::evaluate_king_safety(board, color, &PARAMS),
+            );
+            trace.set(
+                Term::Mobility,
+                color,
+                mobility::evaluate_mobility(board, color, &PARAMS),
+            );
+            if color == game.turn() {
+                trace.set(Term::Tempo, color, PARAMS.tempo);
+            }
+        }
+        trace
+    }
+}
+
+/// Evaluates the position with the parameters from the white player's
+/// point of view, before the score is tapered.
+///
+/// This computes the same terms as the [`TaperedEvaluator`], but without
+/// the incremental updates and the caches, so that any [`Params`] can be
+/// used, see [`Params::unit`].
+pub fn evaluate_params<S: EvalScore>(game: &Game, params: &Params<S>) -> S {
+    let board = game.board();
+    let mut total = S::default();
+    for color in Color::iter() {
+        let (structure, passed) = pawns::evaluate_color_structure(board, color, params);
+        let score = pst::evaluate_psq(board, color, params)
+            + structure
+            + pawns::evaluate_passed_paths(board, color, passed, params)
+            + mobility::evaluate_mobility(board, color, params)
+            + king::evaluate_king_safety(board, color, params);
+        match color {
+            Color::White => total += score,
+            Color::Black => total -= score,
+        }
+    }
+    match game.turn() {
+        Color::White => total += params.tempo.clone(),
+        Color::Black => total -= params.tempo.clone(),
+    }
+    total
+}
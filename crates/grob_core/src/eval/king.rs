@@ -0,0 +1,147 @@
+//! [King safety] evaluation
+//!
+//! [King safety]: https://www.chessprogramming.org/King_Safety
+
+use strum::EnumCount;
+
+use crate::{
+    eval::{EvalScore, Params, SAFETY_SIZE},
+    BitBoard, Board, Color, File, Piece, Rank,
+};
+
+/// Weight of every attack on the king zone, indexed by the attacking piece.
+const ATTACK_WEIGHTS: [i32; Piece::COUNT] = [0, 2, 2, 3, 5, 0];
+
+/// Pieces whose attacks on the king zone are counted.
+const ATTACKING_PIECES: [Piece; 4] = [Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen];
+
+/// The king zone attacks are only penalized if there are at least this many attackers.
+const MIN_ATTACKERS: usize = 2;
+
+/// Evaluates the safety of the king of a player.
+pub fn evaluate_king_safety<S: EvalScore>(board: &Board, color: Color, params: &Params<S>) -> S {
+    evaluate_king_zone(board, color, params) + evaluate_pawn_shelter(board, color, params)
+}
+
+/// Evaluates the enemy attacks on the squares around the king of a player.
+fn evaluate_king_zone<S: EvalScore>(board: &Board, color: Color, params: &Params<S>) -> S {
+    let king = board.get_king(color);
+    let zone = BitBoard::king_attacks(king) | BitBoard::from(king);
+    let mut attackers = BitBoard::EMPTY;
+    let mut weight = 0;
+    for sq in zone {
+        let sq_attackers = board.get_color_attackers_to(sq, !color);
+        for piece in ATTACKING_PIECES {
+            let count = (sq_attackers & board.get_piece(piece)).count() as i32;
+            weight += ATTACK_WEIGHTS[piece as usize] * count;
+        }
+        attackers |= sq_attackers;
+    }
+
+    let attackers = attackers & !(board.get_piece(Piece::Pawn) | board.get_piece(Piece::King));
+    if attackers.count() < MIN_ATTACKERS {
+        return S::default();
+    }
+    params.safety[(weight as usize).min(SAFETY_SIZE - 1)].clone()
+}
+
+/// Evaluates the pawns in front of the king of a player
+/// and the open files next to it.
+fn evaluate_pawn_shelter<S: EvalScore>(board: &Board, color: Color, params: &Params<S>) -> S {
+    let king = board.get_king(color);
+    let ours = board.get_color_piece(color, Piece::Pawn);
+    let theirs = board.get_color_piece(!color, Piece::Pawn);
+    let king_rank = color.mirror_rank(king.rank()) as u8;
+    // The shield pawns are only relevant while the king stays home.
+    let shield_ranks = (king_rank <= Rank::R2 as u8).then(|| {
+        let rank_bb = |offset| {
+            let rank = Rank::from_repr(king_rank + offset).unwrap();
+            BitBoard::from_rank(color.mirror_rank(rank))
+        };
+        (rank_bb(1), rank_bb(2))
+    });
+    // A king on the edge is sheltered by the same files as the one next to it.
+    let center = (king.file() as u8).clamp(File::B as u8, File::G as u8);
+
+    let mut score = S::default();
+    for file in center - 1..=center + 1 {
+        let file = BitBoard::from_file(File::from_repr(file).unwrap());
+        let own_pawns = ours & file;
+        if own_pawns.is_empty() {
+            score += match (theirs & file).is_empty() {
+                true => params.open_file.clone(),
+                false => params.semi_open_file.clone(),
+            };
+        }
+        if let Some((near, far)) = shield_ranks {
+            if (own_pawns & near).is_empty() {
+                score += match (own_pawns & far).is_empty() {
+                    true => params.shield_missing.clone(),
+                    false => params.shield_advanced.clone(),
+                };
+            }
+        }
+    }
+    score
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        eval::{TaperedScore, PARAMS},
+        Game,
+    };
+
+    use super::*;
+
+    fn board(fen: &str) -> Board {
+        *Game::try_from_fen(fen).unwrap().board()
+    }
+
+    #[test]
+    fn test_shelter() {
+        let intact = board("6k1/5ppp/8/8/8/8/5PPP/6K1 w - - 0 1");
+        assert_eq!(
+            evaluate_pawn_shelter(&intact, Color::White, &PARAMS),
+            TaperedScore::ZERO
+        );
+        assert_eq!(
+            evaluate_pawn_shelter(&intact, Color::Black, &PARAMS),
+            TaperedScore::ZERO
+        );
+
+        let weakened = board("6k1/5ppp/8/8/8/6P1/5P2/6K1 w - - 0 1");
+        assert_eq!(
+            evaluate_pawn_shelter(&weakened, Color::White, &PARAMS),
+            PARAMS.shield_advanced + PARAMS.shield_missing + PARAMS.semi_open_file
+        );
+    }
+
+    #[test]
+    fn test_shelter_ignores_walking_king() {
+        let walking = board("6k1/5ppp/8/8/8/6K1/8/8 w - - 0 1");
+        assert_eq!(
+            evaluate_pawn_shelter(&walking, Color::White, &PARAMS),
+            PARAMS.semi_open_file * 3
+        );
+    }
+
+    #[test]
+    fn test_king_zone() {
+        // A lone queen is not enough for an attack.
+        let queen = board("6k1/5ppp/8/6Q1/8/8/8/6K1 w - - 0 1");
+        assert_eq!(
+            evaluate_king_zone(&queen, Color::Black, &PARAMS),
+            TaperedScore::ZERO
+        );
+
+        // Both the queen and the knight attack g7.
+        let attack = board("6k1/5ppp/8/6QN/8/8/8/6K1 w - - 0 1");
+        let weight = ATTACK_WEIGHTS[Piece::Queen as usize] + ATTACK_WEIGHTS[Piece::Knight as usize];
+        assert_eq!(
+            evaluate_king_zone(&attack, Color::Black, &PARAMS),
+            PARAMS.safety[weight as usize]
+        );
+        assert!(PARAMS.safety[weight as usize].mg < 0);
+    }
+}
@@ -0,0 +1,98 @@
+//! [Mobility] evaluation
+//!
+//! [Mobility]: https://www.chessprogramming.org/Mobility
+
+use strum::EnumCount;
+
+use crate::{
+    eval::{EvalScore, Params},
+    BitBoard, Board, Color, Piece, Square,
+};
+
+/// Typical number of squares a piece can move to, indexed by piece.
+///
+/// Pieces with fewer moves are penalized, pieces with more get a bonus.
+const MOBILITY_BASE: [i32; Piece::COUNT] = [0, 4, 6, 7, 13, 0];
+
+/// Pieces that get the mobility bonus.
+const MOBILE_PIECES: [Piece; 4] = [Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen];
+
+/// Evaluates the mobility of the pieces of a player.
+///
+/// Squares occupied by the own pieces or attacked by the enemy pawns are
+/// not counted, since the piece can not move there or would be lost.
+pub fn evaluate_mobility<S: EvalScore>(board: &Board, color: Color, params: &Params<S>) -> S {
+    let area = !(board.get_color(color) | pawn_attacks(board, !color));
+    let mut score = S::default();
+    for piece in MOBILE_PIECES {
+        for sq in board.get_color_piece(color, piece) {
+            let moves = (piece_attacks(board, piece, sq) & area).count() as i32;
+            let moves = moves - MOBILITY_BASE[piece as usize];
+            score += params.mobility[piece as usize].clone() * moves;
+        }
+    }
+    score
+}
+
+/// Returns the squares attacked by the pawns of the player.
+pub fn pawn_attacks(board: &Board, color: Color) -> BitBoard {
+    board
+        .get_color_piece(color, Piece::Pawn)
+        .fold(BitBoard::EMPTY, |attacks, sq| {
+            attacks | BitBoard::pawn_attacks(sq, color)
+        })
+}
+
+/// Returns the squares attacked by a piece other than pawn.
+pub fn piece_attacks(board: &Board, piece: Piece, sq: Square) -> BitBoard {
+    let occupance = board.get_occupance();
+    match piece {
+        Piece::Pawn => unreachable!("pawn attacks depend on the color"),
+        Piece::Knight => BitBoard::knight_attacks(sq),
+        Piece::Bishop => BitBoard::bishop_attacks(occupance, sq),
+        Piece::Rook => BitBoard::rook_attacks(occupance, sq),
+        Piece::Queen => BitBoard::queen_attacks(occupance, sq),
+        Piece::King => BitBoard::king_attacks(sq),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        eval::{TaperedScore, PARAMS},
+        Game,
+    };
+
+    use super::*;
+
+    fn mobility(fen: &str, color: Color) -> TaperedScore {
+        evaluate_mobility(Game::try_from_fen(fen).unwrap().board(), color, &PARAMS)
+    }
+
+    #[test]
+    fn test_centralized_knight() {
+        // 8 moves in the center against 2 moves in the corner.
+        let center = mobility("4k3/8/8/8/3N4/8/8/4K3 w - - 0 1", Color::White);
+        let corner = mobility("4k3/8/8/8/8/8/8/N3K3 w - - 0 1", Color::White);
+        assert_eq!(center, TaperedScore::new(16, 16));
+        assert_eq!(corner, TaperedScore::new(-8, -8));
+    }
+
+    #[test]
+    fn test_pawn_attacks_are_avoided() {
+        // The black pawn attacks c5 and e5.
+        let free = mobility("4k3/8/8/8/3B4/8/8/4K3 w - - 0 1", Color::White);
+        let attacked = mobility("4k3/8/3p4/8/3B4/8/8/4K3 w - - 0 1", Color::White);
+        assert_eq!(
+            free.mg - attacked.mg,
+            2 * PARAMS.mobility[Piece::Bishop as usize].mg
+        );
+    }
+
+    #[test]
+    fn test_symmetry() {
+        let white = mobility("4k3/8/8/8/3Q4/8/8/4K3 w - - 0 1", Color::White);
+        let black = mobility("4k3/8/8/3q4/8/8/8/4K3 w - - 0 1", Color::Black);
+        assert_eq!(white, black);
+    }
+}
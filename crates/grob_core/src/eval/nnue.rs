@@ -0,0 +1,208 @@
+//! [NNUE] evaluation
+//!
+//! The network has a single hidden layer with HalfKA inputs: every piece
+//! (kings included) is a feature relative to the square of the king of the
+//! player whose point of view ("perspective") is taken. The hidden layer
+//! ("accumulator") is computed for both perspectives, so that it can be
+//! updated incrementally as the pieces move (see [`Accumulator`]):
+//!
+//! ```text
+//! (64 king squares × 12 pieces × 64 squares) → H    ×2 perspectives
+//! [clipped ReLU(side to move), clipped ReLU(other side)] (2H) → 1
+//! ```
+//!
+//! # File format
+//!
+//! All numbers are little-endian.
+//!
+//! | Field           | Type                        |
+//! |-----------------|-----------------------------|
+//! | magic           | `b"GROBNNUE"`               |
+//! | version         | `u32`, currently 1          |
+//! | hidden size `H` | `u32`                       |
+//! | feature weights | `i16` × [`FEATURES`] × `H`  |
+//! | feature biases  | `i16` × `H`                 |
+//! | output weights  | `i16` × 2`H`                |
+//! | output bias     | `i32`                       |
+//!
+//! Feature weights are stored feature by feature. The hidden layer is
+//! quantized by [`QA`] and the output weights by [`QB`], the output is
+//! multiplied by [`SCALE`] to get the centi-pawns.
+//!
+//! [NNUE]: https://www.chessprogramming.org/NNUE
+
+use std::{error::Error, fmt};
+
+use strum::EnumCount;
+
+use crate::{
+    eval::{Evaluator, TaperedEvaluator, TaperedScore, Term, Trace},
+    Color, Game, Piece, Score, Square,
+};
+
+pub use accumulator::Accumulator;
+
+mod accumulator;
+mod simd;
+
+/// Number of input features of a single perspective.
+pub const FEATURES: usize = Square::COUNT * 2 * Piece::COUNT * Square::COUNT;
+/// Largest supported hidden layer size.
+pub const MAX_HIDDEN: usize = 1024;
+/// Quantization of the hidden layer, it is clipped to `0..=QA`.
+pub const QA: i32 = 255;
+/// Quantization of the output weights.
+pub const QB: i32 = 64;
+/// Scale of the network output in centi-pawns.
+pub const SCALE: i32 = 400;
+
+/// File signature of a network.
+const MAGIC: &[u8; 8] = b"GROBNNUE";
+/// Supported version of the file format.
+const VERSION: u32 = 1;
+
+/// An error that originated from [`Network`] parsing.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ParseNetworkError {
+    /// The file does not start with the network signature.
+    BadMagic,
+    /// The file format version is not supported.
+    BadVersion,
+    /// The hidden layer size is zero, too large or not a multiple of 16.
+    BadHiddenSize,
+    /// The size of the file does not match the hidden layer size.
+    BadLength,
+}
+
+impl fmt::Display for ParseNetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl Error for ParseNetworkError {}
+
+/// Weights of an NNUE network.
+#[derive(Clone)]
+pub struct Network {
+    hidden: usize,
+    feature_weights: Box<[i16]>,
+    feature_biases: Box<[i16]>,
+    output_weights: Box<[i16]>,
+    output_bias: i32,
+}
+
+impl fmt::Debug for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Network")
+            .field("hidden", &self.hidden)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Network {
+    /// Parses a network from the contents of a file.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ParseNetworkError> {
+        let (magic, bytes) = bytes
+            .split_first_chunk::<8>()
+            .ok_or(ParseNetworkError::BadMagic)?;
+        if magic != MAGIC {
+            return Err(ParseNetworkError::BadMagic);
+        }
+        let (version, bytes) = bytes
+            .split_first_chunk::<4>()
+            .ok_or(ParseNetworkError::BadVersion)?;
+        if u32::from_le_bytes(*version) != VERSION {
+            return Err(ParseNetworkError::BadVersion);
+        }
+        let (hidden, bytes) = bytes
+            .split_first_chunk::<4>()
+            .ok_or(ParseNetworkError::BadHiddenSize)?;
+        let hidden = u32::from_le_bytes(*hidden) as usize;
+        if hidden == 0 || hidden > MAX_HIDDEN || !hidden.is_multiple_of(16) {
+            return Err(ParseNetworkError::BadHiddenSize);
+        }
+
+        let i16_count = FEATURES * hidden + hidden + 2 * hidden;
+        if bytes.len() != i16_count * 2 + 4 {
+            return Err(ParseNetworkError::BadLength);
+        }
+        let (weights, bias) = bytes.split_at(i16_count * 2);
+        let mut weights = weights
+            .chunks_exact(2)
+            .map(|chunk| i16::from_le_bytes([chunk[0], chunk[1]]));
+        let mut take = |count| weights.by_ref().take(count).collect::<Box<[i16]>>();
+
+        Ok(Self {
+            hidden,
+            feature_weights: take(FEATURES * hidden),
+            feature_biases: take(hidden),
+            output_weights: take(2 * hidden),
+            output_bias: i32::from_le_bytes(bias.try_into().unwrap()),
+        })
+    }
+    /// Returns the size of the hidden layer of a single perspective.
+    pub fn hidden_size(&self) -> usize {
+        self.hidden
+    }
+    /// Returns the weights of an input feature.
+    fn feature_weights(&self, feature: usize) -> &[i16] {
+        &self.feature_weights[feature * self.hidden..(feature + 1) * self.hidden]
+    }
+    /// Computes the output from the hidden layers of both perspectives.
+    ///
+    /// Returns the centi-pawns for the player who is about to make a turn.
+    fn output(&self, us: &[i16], them: &[i16]) -> i32 {
+        let (us_weights, them_weights) = self.output_weights.split_at(self.hidden);
+        let sum = simd::output(us, us_weights) + simd::output(them, them_weights);
+        let sum = sum + self.output_bias as i64;
+        (sum * SCALE as i64 / (QA * QB) as i64) as i32
+    }
+}
+
+/// An [`Evaluator`] that uses the NNUE network of the [`Game`].
+///
+/// The network is set with [`Game::set_network`], positions without
+/// a network are evaluated by the [`TaperedEvaluator`] instead.
+#[derive(Debug, Clone, Default)]
+pub struct NnueEvaluator {
+    fallback: TaperedEvaluator,
+}
+
+impl NnueEvaluator {
+    /// Returns a boxed [`NnueEvaluator`], this is an [`EvaluatorFactory`].
+    ///
+    /// [`EvaluatorFactory`]: crate::eval::EvaluatorFactory
+    pub fn boxed() -> Box<dyn Evaluator> {
+        Box::<Self>::default()
+    }
+}
+
+impl Evaluator for NnueEvaluator {
+    fn evaluate(&mut self, game: &Game) -> Score {
+        match game.nnue() {
+            Some(accumulator) => Score::Cp(accumulator.evaluate(game.turn())),
+            None => self.fallback.evaluate(game),
+        }
+    }
+    fn trace(&mut self, game: &Game) -> Trace {
+        let Some(accumulator) = game.nnue() else {
+            return self.fallback.trace(game);
+        };
+        // The network does not split its output into the terms.
+        let score = accumulator.evaluate(game.turn());
+        let mut trace = Trace::new(game.phase(), game.turn());
+        trace.set(Term::Network, game.turn(), TaperedScore::new(score, score));
+        trace
+    }
+}
+
+/// Returns the input feature of a piece from the perspective of a player.
+///
+/// Black's perspective is mirrored, so that both players see
+/// their own pieces as if they were white.
+fn feature(perspective: Color, king: Square, color: Color, piece: Piece, sq: Square) -> usize {
+    let orient = |sq: Square| perspective.mirror_square(sq) as usize;
+    let piece = (color != perspective) as usize * Piece::COUNT + piece as usize;
+    (orient(king) * 2 * Piece::COUNT + piece) * Square::COUNT + orient(sq)
+}
@@ -0,0 +1,126 @@
+//! Incrementally updated hidden layer of the network.
+
+use std::{fmt, sync::Arc};
+
+use strum::{EnumCount, IntoEnumIterator};
+
+use crate::{
+    eval::nnue::{
+        feature,
+        simd::{add, sub},
+        Network,
+    },
+    Board, Color, Piece, Square,
+};
+
+/// The hidden layer of a [`Network`] for both perspectives.
+///
+/// [`Game`](crate::Game) keeps it up to date as the pieces are added,
+/// removed and moved on the board, so that only the weights of the changed
+/// features have to be added or subtracted. When a king moves, all features
+/// of its perspective change, so that perspective is recomputed from scratch.
+///
+/// The values use wrapping arithmetic, so that unmaking a move always
+/// restores them exactly.
+#[derive(Clone)]
+pub struct Accumulator {
+    network: Arc<Network>,
+    values: [Box<[i16]>; Color::COUNT],
+}
+
+impl fmt::Debug for Accumulator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Accumulator")
+            .field("network", &self.network)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for Accumulator {
+    /// Accumulators are equal if they have the same values for the same network.
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.network, &other.network) && self.values == other.values
+    }
+}
+
+impl Accumulator {
+    /// Computes the accumulator for the board from scratch.
+    pub fn new(network: Arc<Network>, board: &Board) -> Self {
+        let empty = vec![0; network.hidden_size()].into_boxed_slice();
+        let mut accumulator = Self {
+            network,
+            values: [empty.clone(), empty],
+        };
+        for perspective in Color::iter() {
+            accumulator.refresh(board, perspective);
+        }
+        accumulator
+    }
+    /// Returns the network of the accumulator.
+    pub fn network(&self) -> &Arc<Network> {
+        &self.network
+    }
+    /// Returns the evaluation of the position for the player who is about to make a turn.
+    pub fn evaluate(&self, turn: Color) -> i32 {
+        self.network
+            .output(&self.values[turn as usize], &self.values[!turn as usize])
+    }
+    /// Updates the accumulator after a piece was added to the board.
+    pub(crate) fn add(&mut self, board: &Board, color: Color, piece: Piece, sq: Square) {
+        debug_assert_ne!(piece, Piece::King);
+        for perspective in Color::iter() {
+            let feature = feature(perspective, board.get_king(perspective), color, piece, sq);
+            add(
+                &mut self.values[perspective as usize],
+                self.network.feature_weights(feature),
+            );
+        }
+    }
+    /// Updates the accumulator after a piece was removed from the board.
+    pub(crate) fn remove(&mut self, board: &Board, color: Color, piece: Piece, sq: Square) {
+        debug_assert_ne!(piece, Piece::King);
+        for perspective in Color::iter() {
+            let feature = feature(perspective, board.get_king(perspective), color, piece, sq);
+            sub(
+                &mut self.values[perspective as usize],
+                self.network.feature_weights(feature),
+            );
+        }
+    }
+    /// Updates the accumulator after a piece was moved on the board.
+    pub(crate) fn move_piece(
+        &mut self,
+        board: &Board,
+        color: Color,
+        piece: Piece,
+        from: Square,
+        to: Square,
+    ) {
+        for perspective in Color::iter() {
+            if piece == Piece::King && perspective == color {
+                self.refresh(board, perspective);
+                continue;
+            }
+            let king = board.get_king(perspective);
+            let values = &mut self.values[perspective as usize];
+            let from = feature(perspective, king, color, piece, from);
+            let to = feature(perspective, king, color, piece, to);
+            sub(values, self.network.feature_weights(from));
+            add(values, self.network.feature_weights(to));
+        }
+    }
+    /// Recomputes the accumulator of the perspective from scratch.
+    fn refresh(&mut self, board: &Board, perspective: Color) {
+        let values = &mut self.values[perspective as usize];
+        values.copy_from_slice(&self.network.feature_biases);
+        let king = board.get_king(perspective);
+        for color in Color::iter() {
+            for piece in Piece::iter() {
+                for sq in board.get_color_piece(color, piece) {
+                    let feature = feature(perspective, king, color, piece, sq);
+                    add(values, self.network.feature_weights(feature));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,187 @@
+//! Accumulator updates and the output layer of the network.
+//!
+//! Uses AVX2 when the CPU supports it and falls back to the scalar code otherwise.
+//! Both produce the same results.
+
+use crate::eval::nnue::QA;
+
+/// Adds the feature weights to the accumulator values, wrapping on overflow.
+///
+/// Both slices must have the same length, a multiple of 16.
+pub fn add(values: &mut [i16], weights: &[i16]) {
+    debug_assert_eq!(values.len(), weights.len());
+    debug_assert!(values.len().is_multiple_of(16));
+
+    #[cfg(target_arch = "x86_64")]
+    if is_x86_feature_detected!("avx2") {
+        // SAFETY: the CPU supports AVX2.
+        return unsafe { avx2::add(values, weights) };
+    }
+    scalar_add(values, weights)
+}
+
+/// Subtracts the feature weights from the accumulator values, wrapping on overflow.
+///
+/// Both slices must have the same length, a multiple of 16.
+pub fn sub(values: &mut [i16], weights: &[i16]) {
+    debug_assert_eq!(values.len(), weights.len());
+    debug_assert!(values.len().is_multiple_of(16));
+
+    #[cfg(target_arch = "x86_64")]
+    if is_x86_feature_detected!("avx2") {
+        // SAFETY: the CPU supports AVX2.
+        return unsafe { avx2::sub(values, weights) };
+    }
+    scalar_sub(values, weights)
+}
+
+/// Returns the dot product of the clipped hidden layer and the weights.
+///
+/// The hidden layer size must be a multiple of 16 and at most
+/// [`MAX_HIDDEN`](super::MAX_HIDDEN), so that the intermediate sums fit in `i32`.
+pub fn output(values: &[i16], weights: &[i16]) -> i64 {
+    debug_assert_eq!(values.len(), weights.len());
+    debug_assert!(values.len().is_multiple_of(16));
+
+    #[cfg(target_arch = "x86_64")]
+    if is_x86_feature_detected!("avx2") {
+        // SAFETY: the CPU supports AVX2.
+        return unsafe { avx2::output(values, weights) };
+    }
+    scalar_output(values, weights)
+}
+
+/// Scalar version of [`add`].
+fn scalar_add(values: &mut [i16], weights: &[i16]) {
+    for (value, weight) in values.iter_mut().zip(weights) {
+        *value = value.wrapping_add(*weight);
+    }
+}
+
+/// Scalar version of [`sub`].
+fn scalar_sub(values: &mut [i16], weights: &[i16]) {
+    for (value, weight) in values.iter_mut().zip(weights) {
+        *value = value.wrapping_sub(*weight);
+    }
+}
+
+/// Scalar version of [`output`].
+fn scalar_output(values: &[i16], weights: &[i16]) -> i64 {
+    values
+        .iter()
+        .zip(weights)
+        .map(|(&value, &weight)| value.clamp(0, QA as i16) as i64 * weight as i64)
+        .sum()
+}
+
+#[cfg(target_arch = "x86_64")]
+mod avx2 {
+    use std::arch::x86_64::*;
+
+    use crate::eval::nnue::QA;
+
+    /// AVX2 version of [`super::add`].
+    ///
+    /// # Safety
+    /// The CPU must support AVX2.
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn add(values: &mut [i16], weights: &[i16]) {
+        for (values, weights) in values.chunks_exact_mut(16).zip(weights.chunks_exact(16)) {
+            // SAFETY: both chunks have 16 elements, which is 256 bits.
+            unsafe {
+                let sum = _mm256_add_epi16(
+                    _mm256_loadu_si256(values.as_ptr().cast()),
+                    _mm256_loadu_si256(weights.as_ptr().cast()),
+                );
+                _mm256_storeu_si256(values.as_mut_ptr().cast(), sum);
+            }
+        }
+    }
+
+    /// AVX2 version of [`super::sub`].
+    ///
+    /// # Safety
+    /// The CPU must support AVX2.
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn sub(values: &mut [i16], weights: &[i16]) {
+        for (values, weights) in values.chunks_exact_mut(16).zip(weights.chunks_exact(16)) {
+            // SAFETY: both chunks have 16 elements, which is 256 bits.
+            unsafe {
+                let difference = _mm256_sub_epi16(
+                    _mm256_loadu_si256(values.as_ptr().cast()),
+                    _mm256_loadu_si256(weights.as_ptr().cast()),
+                );
+                _mm256_storeu_si256(values.as_mut_ptr().cast(), difference);
+            }
+        }
+    }
+
+    /// AVX2 version of [`super::output`].
+    ///
+    /// # Safety
+    /// The CPU must support AVX2.
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn output(values: &[i16], weights: &[i16]) -> i64 {
+        let zero = _mm256_setzero_si256();
+        let max = _mm256_set1_epi16(QA as i16);
+        let mut sum = _mm256_setzero_si256();
+        for (values, weights) in values.chunks_exact(16).zip(weights.chunks_exact(16)) {
+            // SAFETY: both chunks have 16 elements, which is 256 bits.
+            let (values, weights) = unsafe {
+                (
+                    _mm256_loadu_si256(values.as_ptr().cast()),
+                    _mm256_loadu_si256(weights.as_ptr().cast()),
+                )
+            };
+            let clipped = _mm256_min_epi16(_mm256_max_epi16(values, zero), max);
+            sum = _mm256_add_epi32(sum, _mm256_madd_epi16(clipped, weights));
+        }
+
+        let mut lanes = [0i32; 8];
+        // SAFETY: the array has 8 elements, which is 256 bits.
+        unsafe { _mm256_storeu_si256(lanes.as_mut_ptr().cast(), sum) };
+        lanes.iter().map(|&lane| lane as i64).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns `len` pseudo-random numbers, the same for every seed.
+    fn random(seed: u64, len: usize) -> Vec<i16> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                (state >> 48) as i16
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_add_sub_match_scalar() {
+        // Full range values, so that the wrapping is exercised as well.
+        let values = random(0xACC, 256);
+        let weights = random(0xF00D, 256);
+
+        let (mut simd, mut scalar) = (values.clone(), values.clone());
+        add(&mut simd, &weights);
+        scalar_add(&mut scalar, &weights);
+        assert_eq!(simd, scalar);
+
+        sub(&mut simd, &weights);
+        scalar_sub(&mut scalar, &weights);
+        assert_eq!(simd, scalar);
+        assert_eq!(simd, values);
+    }
+
+    #[test]
+    fn test_output_matches_scalar() {
+        let values: Vec<i16> = random(0x5EED, 256).iter().map(|&v| v / 64).collect();
+        let weights = random(0xBEEF, 256);
+        assert_eq!(output(&values, &weights), scalar_output(&values, &weights));
+    }
+}
@@ -0,0 +1,278 @@
+//! Tunable parameters of the [`TaperedEvaluator`](crate::eval::TaperedEvaluator)
+//!
+//! The evaluation terms are written generically over the [`EvalScore`]
+//! they are summed into. Evaluating with [`PARAMS`] gives the usual
+//! [`TaperedScore`](crate::eval::TaperedScore), while evaluating with [`Params::unit`] gives the
+//! [`Coefficients`] of every parameter, which is what the [Texel tuning]
+//! needs, since the evaluation is linear in its parameters.
+//!
+//! [Texel tuning]: https://www.chessprogramming.org/Texel%27s_Tuning_Method
+
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+
+use strum::EnumCount;
+
+use crate::{Piece, Rank, Square};
+
+pub use values::PARAMS;
+
+mod values;
+
+/// Number of entries in the [`Params::safety`] table.
+pub const SAFETY_SIZE: usize = 64;
+
+/// A score the evaluation terms can be summed into.
+pub trait EvalScore:
+    Clone
+    + Default
+    + Add<Output = Self>
+    + AddAssign
+    + Sub<Output = Self>
+    + SubAssign
+    + Neg<Output = Self>
+    + Mul<i32, Output = Self>
+    + Div<i32, Output = Self>
+{
+}
+
+impl<T> EvalScore for T where
+    T: Clone
+        + Default
+        + Add<Output = Self>
+        + AddAssign
+        + Sub<Output = Self>
+        + SubAssign
+        + Neg<Output = Self>
+        + Mul<i32, Output = Self>
+        + Div<i32, Output = Self>
+{
+}
+
+/// Parameters of the evaluation.
+///
+/// All scores are bonuses for the player the term is evaluated for,
+/// so the penalties are negative.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Params<S> {
+    /// Values of the pieces.
+    pub material: [S; Piece::COUNT],
+    /// [Piece-square tables], written from the white player's point of view
+    /// with the 8th rank first, just like the board is usually drawn.
+    ///
+    /// [Piece-square tables]: https://www.chessprogramming.org/Piece-Square_Tables
+    pub pst: [[S; Square::COUNT]; Piece::COUNT],
+    /// Penalty for every pawn that has a friendly pawn in front of it.
+    pub doubled: S,
+    /// Penalty for a pawn with no friendly pawns on the adjacent files.
+    pub isolated: S,
+    /// Penalty for a pawn that can not be supported by the friendly pawns
+    /// and can not advance safely.
+    pub backward: S,
+    /// Bonus for a pawn that is defended by a friendly pawn or stands next to one,
+    /// indexed by the relative rank.
+    pub connected: [S; Rank::COUNT],
+    /// Bonus for a passed pawn, indexed by the relative rank.
+    pub passed: [S; Rank::COUNT],
+    /// Additional bonus for a passed pawn with no pieces on its way
+    /// to the promotion square, indexed by the relative rank.
+    ///
+    /// Half of the bonus is given if only the square in front of the pawn is free.
+    pub passed_free_path: [S; Rank::COUNT],
+    /// Bonus for every square a piece can move to, indexed by piece.
+    pub mobility: [S; Piece::COUNT],
+    /// Penalty for the attacks on the king zone, indexed by their total weight.
+    ///
+    /// The penalty should grow quadratically, since a lone attacker is rarely
+    /// dangerous, while a coordinated attack often decides the game.
+    pub safety: [S; SAFETY_SIZE],
+    /// Penalty for a shield pawn that has advanced two squares from the king.
+    pub shield_advanced: S,
+    /// Penalty for a missing shield pawn.
+    pub shield_missing: S,
+    /// Penalty for a file near the king without the own pawns.
+    pub semi_open_file: S,
+    /// Penalty for a file near the king without any pawns.
+    pub open_file: S,
+    /// Bonus for the player who is about to make a turn.
+    pub tempo: S,
+}
+
+impl<S> Params<S> {
+    /// Creates the parameters by calling the function for every one of them
+    /// in the order of [`Self::iter`].
+    pub fn from_fn(mut f: impl FnMut() -> S) -> Self {
+        fn array<S, const N: usize>(f: &mut impl FnMut() -> S) -> [S; N] {
+            std::array::from_fn(|_| f())
+        }
+        Self {
+            material: array(&mut f),
+            pst: std::array::from_fn(|_| array(&mut f)),
+            doubled: f(),
+            isolated: f(),
+            backward: f(),
+            connected: array(&mut f),
+            passed: array(&mut f),
+            passed_free_path: array(&mut f),
+            mobility: array(&mut f),
+            safety: array(&mut f),
+            shield_advanced: f(),
+            shield_missing: f(),
+            semi_open_file: f(),
+            open_file: f(),
+            tempo: f(),
+        }
+    }
+    /// Returns an iterator over all parameters in the order of the fields.
+    pub fn iter(&self) -> impl Iterator<Item = &S> {
+        self.material
+            .iter()
+            .chain(self.pst.iter().flatten())
+            .chain([&self.doubled, &self.isolated, &self.backward])
+            .chain(&self.connected)
+            .chain(&self.passed)
+            .chain(&self.passed_free_path)
+            .chain(&self.mobility)
+            .chain(&self.safety)
+            .chain([
+                &self.shield_advanced,
+                &self.shield_missing,
+                &self.semi_open_file,
+                &self.open_file,
+                &self.tempo,
+            ])
+    }
+    /// Returns the number of parameters.
+    pub fn count(&self) -> usize {
+        self.iter().count()
+    }
+}
+
+impl Params<Coefficients> {
+    /// Returns the parameters where each one is its own unit [`Coefficients`],
+    /// indexed in the order of [`Self::iter`].
+    pub fn unit() -> Self {
+        let mut index = 0;
+        Self::from_fn(|| {
+            index += 1;
+            Coefficients::unit(index - 1)
+        })
+    }
+}
+
+/// A linear combination of the parameters.
+///
+/// The same coefficient is used for the middlegame and the endgame
+/// scores of a parameter.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Coefficients {
+    /// Pairs of parameter indices and coefficients, possibly repeated.
+    terms: Vec<(u32, f32)>,
+}
+
+impl Coefficients {
+    /// Returns the coefficients of a single parameter.
+    pub fn unit(index: usize) -> Self {
+        Self {
+            terms: vec![(index as u32, 1.0)],
+        }
+    }
+    /// Returns the pairs of parameter indices and non-zero coefficients
+    /// ordered by the index.
+    pub fn terms(&self) -> impl Iterator<Item = (usize, f32)> {
+        let mut terms = self.terms.clone();
+        terms.sort_unstable_by_key(|&(index, _)| index);
+        let mut merged: Vec<(u32, f32)> = Vec::with_capacity(terms.len());
+        for (index, coefficient) in terms {
+            match merged.last_mut() {
+                Some(last) if last.0 == index => last.1 += coefficient,
+                _ => merged.push((index, coefficient)),
+            }
+        }
+        merged
+            .into_iter()
+            .filter(|&(_, coefficient)| coefficient != 0.0)
+            .map(|(index, coefficient)| (index as usize, coefficient))
+    }
+    /// Scales all coefficients.
+    fn scale(mut self, factor: f32) -> Self {
+        for (_, coefficient) in &mut self.terms {
+            *coefficient *= factor;
+        }
+        self
+    }
+}
+
+impl Add for Coefficients {
+    type Output = Self;
+
+    fn add(mut self, rhs: Self) -> Self {
+        self += rhs;
+        self
+    }
+}
+
+impl AddAssign for Coefficients {
+    fn add_assign(&mut self, rhs: Self) {
+        self.terms.extend(rhs.terms);
+    }
+}
+
+impl Sub for Coefficients {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        self + -rhs
+    }
+}
+
+impl SubAssign for Coefficients {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self += -rhs;
+    }
+}
+
+impl Neg for Coefficients {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        self.scale(-1.0)
+    }
+}
+
+impl Mul<i32> for Coefficients {
+    type Output = Self;
+
+    fn mul(self, rhs: i32) -> Self {
+        self.scale(rhs as f32)
+    }
+}
+
+impl Div<i32> for Coefficients {
+    type Output = Self;
+
+    fn div(self, rhs: i32) -> Self {
+        self.scale(1.0 / rhs as f32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unit_indices() {
+        let unit = Params::unit();
+        assert_eq!(unit.count(), PARAMS.count());
+        for (index, coefficients) in unit.iter().enumerate() {
+            assert_eq!(coefficients.terms().collect::<Vec<_>>(), [(index, 1.0)]);
+        }
+    }
+
+    #[test]
+    fn test_coefficients_merge() {
+        let a = Coefficients::unit(3);
+        let b = Coefficients::unit(1);
+        let sum = (a.clone() * 3 - b.clone() + b / 2) - a;
+        assert_eq!(sum.terms().collect::<Vec<_>>(), [(1, -0.5), (3, 2.0)]);
+    }
+}
@@ -0,0 +1,255 @@
+//! [Pawn structure] evaluation
+//!
+//! The terms that only depend on the placement of the pawns are cached
+//! in the [`PawnTable`] by [`Game::pawn_zobrist`], since the pawn structure
+//! rarely changes between the nodes of the search tree.
+//!
+//! [Pawn structure]: https://www.chessprogramming.org/Pawn_Structure
+
+use strum::{EnumCount, IntoEnumIterator};
+
+use crate::{
+    eval::{EvalScore, Params, TaperedScore, PARAMS},
+    BitBoard, Board, Color, File, Game, Piece, Rank,
+};
+
+/// Number of entries in the [`PawnTable`].
+const PAWN_TABLE_SIZE: usize = 1 << 14;
+
+/// Pawn structure terms of a position.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PawnEntry {
+    /// [`Game::pawn_zobrist`] of the position.
+    key: u64,
+    /// Score of the pawn structure of each color.
+    pub score: [TaperedScore; Color::COUNT],
+    /// Passed pawns of each color.
+    pub passed: [BitBoard; Color::COUNT],
+}
+
+/// A cache of the pawn structure terms.
+///
+/// The entries are replaced on every collision.
+#[derive(Debug, Clone)]
+pub struct PawnTable {
+    entries: Box<[PawnEntry]>,
+}
+
+impl Default for PawnTable {
+    fn default() -> Self {
+        // An empty entry has the key of a position without pawns,
+        // whose pawn structure is worth nothing, so it is still correct.
+        Self {
+            entries: vec![PawnEntry::default(); PAWN_TABLE_SIZE].into_boxed_slice(),
+        }
+    }
+}
+
+impl PawnTable {
+    /// Returns the pawn structure terms of the position,
+    /// evaluating them if they are not cached.
+    pub fn probe(&mut self, game: &Game) -> PawnEntry {
+        let key = game.pawn_zobrist();
+        let entry = &mut self.entries[key as usize % PAWN_TABLE_SIZE];
+        if entry.key != key {
+            *entry = evaluate_structure(game.board());
+            entry.key = key;
+        }
+        *entry
+    }
+}
+
+/// Evaluates the pawn structure of both players.
+pub fn evaluate_structure(board: &Board) -> PawnEntry {
+    let mut entry = PawnEntry::default();
+    for color in Color::iter() {
+        let (score, passed) = evaluate_color_structure(board, color, &PARAMS);
+        entry.score[color as usize] = score;
+        entry.passed[color as usize] = passed;
+    }
+    entry
+}
+
+/// Evaluates the pawn structure of a player.
+///
+/// Returns the score for the player and its passed pawns.
+pub fn evaluate_color_structure<S: EvalScore>(
+    board: &Board,
+    color: Color,
+    params: &Params<S>,
+) -> (S, BitBoard) {
+    let ours = board.get_color_piece(color, Piece::Pawn);
+    let theirs = board.get_color_piece(!color, Piece::Pawn);
+    let mut score = S::default();
+    let mut passed = BitBoard::EMPTY;
+
+    for sq in ours {
+        let rank = color.mirror_rank(sq.rank()) as usize;
+        let file = BitBoard::from_file(sq.file());
+        let adjacent = adjacent_files(sq.file());
+        let ahead = ranks_ahead(color, sq.rank());
+        let neighbours = ours & adjacent;
+
+        let is_doubled = !(ours & file & ahead).is_empty();
+        if is_doubled {
+            score += params.doubled.clone();
+        }
+
+        if neighbours.is_empty() {
+            score += params.isolated.clone();
+        } else if (neighbours & !ahead).is_empty() {
+            // All neighbours have already advanced past the pawn,
+            // so it is backward if the enemy pawns control its stop square.
+            let stop = sq.shifted(forward(color));
+            if !(theirs & BitBoard::pawn_attacks(stop, color)).is_empty() {
+                score += params.backward.clone();
+            }
+        }
+
+        let supporters = ours & BitBoard::pawn_attacks(sq, !color);
+        let phalanx = neighbours & BitBoard::from_rank(sq.rank());
+        if !(supporters | phalanx).is_empty() {
+            score += params.connected[rank].clone();
+        }
+
+        if !is_doubled && (theirs & (file | adjacent) & ahead).is_empty() {
+            score += params.passed[rank].clone();
+            passed |= BitBoard::from(sq);
+        }
+    }
+
+    (score, passed)
+}
+
+/// Evaluates how free the way of the passed pawns of a player to the promotion is.
+///
+/// Unlike the pawn structure this depends on the other pieces,
+/// so it can not be cached.
+pub fn evaluate_passed_paths<S: EvalScore>(
+    board: &Board,
+    color: Color,
+    passed: BitBoard,
+    params: &Params<S>,
+) -> S {
+    let occupance = board.get_occupance();
+    let mut score = S::default();
+    for sq in passed {
+        let rank = color.mirror_rank(sq.rank()) as usize;
+        let path = BitBoard::from_file(sq.file()) & ranks_ahead(color, sq.rank());
+        let stop = sq.shifted(forward(color));
+        if (path & occupance).is_empty() {
+            score += params.passed_free_path[rank].clone();
+        } else if !occupance.has_square(stop) {
+            score += params.passed_free_path[rank].clone() / 2;
+        }
+    }
+    score
+}
+
+/// Returns the files next to the file.
+fn adjacent_files(file: File) -> BitBoard {
+    let file = file as u8;
+    let left = file
+        .checked_sub(1)
+        .and_then(File::from_repr)
+        .map_or(BitBoard::EMPTY, BitBoard::from_file);
+    let right = File::from_repr(file + 1).map_or(BitBoard::EMPTY, BitBoard::from_file);
+    left | right
+}
+
+/// Returns the ranks in front of the rank from the player's point of view.
+fn ranks_ahead(color: Color, rank: Rank) -> BitBoard {
+    let rank = color.mirror_rank(rank) as u8;
+    Rank::iter()
+        .filter(|&r| color.mirror_rank(r) as u8 > rank)
+        .fold(BitBoard::EMPTY, |bb, r| bb | BitBoard::from_rank(r))
+}
+
+/// Returns the square offset of the player's pawn pushes.
+fn forward(color: Color) -> i8 {
+    match color {
+        Color::White => File::COUNT as i8,
+        Color::Black => -(File::COUNT as i8),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Square;
+
+    use super::*;
+
+    fn structure(fen: &str) -> PawnEntry {
+        evaluate_structure(Game::try_from_fen(fen).unwrap().board())
+    }
+
+    #[test]
+    fn test_masks() {
+        assert_eq!(adjacent_files(File::A), BitBoard::from_file(File::B));
+        assert_eq!(
+            adjacent_files(File::E),
+            BitBoard::from_file(File::D) | BitBoard::from_file(File::F)
+        );
+        assert_eq!(ranks_ahead(Color::White, Rank::R8), BitBoard::EMPTY);
+        assert_eq!(
+            ranks_ahead(Color::Black, Rank::R2),
+            BitBoard::from_rank(Rank::R1)
+        );
+        assert_eq!(
+            ranks_ahead(Color::White, Rank::R6),
+            BitBoard::from_rank(Rank::R7) | BitBoard::from_rank(Rank::R8)
+        );
+    }
+
+    #[test]
+    fn test_doubled_and_isolated() {
+        // Two isolated pawns on the same file, the rear one is doubled.
+        let entry = structure("4k3/8/8/8/8/4P3/4P3/4K3 w - - 0 1");
+        let white = PARAMS.doubled + PARAMS.isolated + PARAMS.isolated + PARAMS.passed[2];
+        assert_eq!(entry.score, [white, TaperedScore::ZERO]);
+        assert_eq!(
+            entry.passed[Color::White as usize],
+            BitBoard::from(Square::E3)
+        );
+    }
+
+    #[test]
+    fn test_backward() {
+        // The d3 pawn can not advance past the black pawn on e5.
+        let entry = structure("4k3/8/1p6/4p3/2P5/3P4/8/4K3 w - - 0 1");
+        let white = PARAMS.backward + PARAMS.connected[3];
+        assert_eq!(entry.score, [white, PARAMS.isolated + PARAMS.isolated]);
+        assert!(entry.passed.iter().all(|passed| passed.is_empty()));
+    }
+
+    #[test]
+    fn test_passed() {
+        // The a-pawn is passed, the d-pawns block each other.
+        let entry = structure("4k3/8/3p4/P7/3P4/8/8/4K3 b - - 0 1");
+        assert_eq!(
+            entry.passed[Color::White as usize],
+            BitBoard::from(Square::A5)
+        );
+        assert!(entry.passed[Color::Black as usize].is_empty());
+        let white = PARAMS.passed[4] + PARAMS.isolated + PARAMS.isolated;
+        assert_eq!(entry.score, [white, PARAMS.isolated]);
+    }
+
+    #[test]
+    fn test_passed_paths() {
+        let paths = |fen, color: Color| {
+            let game = Game::try_from_fen(fen).unwrap();
+            let entry = evaluate_structure(game.board());
+            evaluate_passed_paths(game.board(), color, entry.passed[color as usize], &PARAMS)
+        };
+        let free = PARAMS.passed_free_path[5];
+        let white = Color::White;
+        assert_eq!(paths("4k3/8/1P6/8/8/8/8/4K3 w - - 0 1", white), free);
+        assert_eq!(paths("1n2k3/8/1P6/8/8/8/8/4K3 w - - 0 1", white), free / 2);
+        assert_eq!(
+            paths("4k3/1n6/1P6/8/8/8/8/4K3 w - - 0 1", white),
+            TaperedScore::ZERO
+        );
+        assert_eq!(paths("4k3/8/8/8/8/1p6/8/4K3 w - - 0 1", Color::Black), free);
+    }
+}
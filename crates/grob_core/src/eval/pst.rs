@@ -0,0 +1,77 @@
+//! Material and [piece-square tables] evaluation
+//!
+//! The scores of every piece on every square are precomputed from [`PARAMS`],
+//! so that [`Game`](crate::Game) can keep their sum up to date incrementally.
+//! The tables started out as the ones from [PeSTO].
+//!
+//! [piece-square tables]: https://www.chessprogramming.org/Piece-Square_Tables
+//! [PeSTO]: https://www.chessprogramming.org/PeSTO%27s_Evaluation_Function
+
+use strum::{EnumCount, IntoEnumIterator};
+
+use crate::{
+    eval::{EvalScore, Params, TaperedScore, PARAMS},
+    Board, Color, Piece, Square,
+};
+
+/// Contribution of the pieces to the game phase.
+const PHASE_WEIGHTS: [i32; Piece::COUNT] = [0, 1, 1, 2, 4, 0];
+
+type SquareScores = [[[TaperedScore; Square::COUNT]; Piece::COUNT]; Color::COUNT];
+
+/// Scores of the white pieces (material included), indexed by color, piece and square.
+///
+/// Scores of the black pieces are negated, so that the sum of all
+/// pieces on the board is the score from the white player's point of view.
+const SQUARE_SCORES: SquareScores = {
+    let mut scores = [[[TaperedScore::ZERO; Square::COUNT]; Piece::COUNT]; Color::COUNT];
+    let mut piece = 0;
+    while piece < Piece::COUNT {
+        let mut sq = 0;
+        while sq < Square::COUNT {
+            let material = PARAMS.material[piece];
+            // Tables have the 8th rank first, so the white squares are flipped.
+            let white = PARAMS.pst[piece][sq ^ 56];
+            let black = PARAMS.pst[piece][sq];
+            let white = TaperedScore::new(material.mg + white.mg, material.eg + white.eg);
+            let black = TaperedScore::new(-(material.mg + black.mg), -(material.eg + black.eg));
+            scores[Color::White as usize][piece][sq] = white;
+            scores[Color::Black as usize][piece][sq] = black;
+            sq += 1;
+        }
+        piece += 1;
+    }
+    scores
+};
+
+/// Returns the score of the piece on the square (material included)
+/// from the white player's point of view.
+#[inline(always)]
+pub(crate) fn square_score(color: Color, piece: Piece, sq: Square) -> TaperedScore {
+    SQUARE_SCORES[color as usize][piece as usize][sq as usize]
+}
+
+/// Returns the material value of the piece.
+pub(crate) const fn material_value(piece: Piece) -> TaperedScore {
+    PARAMS.material[piece as usize]
+}
+
+/// Returns how much the piece contributes to the game phase.
+#[inline(always)]
+pub(crate) fn phase_weight(piece: Piece) -> i32 {
+    PHASE_WEIGHTS[piece as usize]
+}
+
+/// Evaluates the material and the placement of the pieces of a player.
+pub(crate) fn evaluate_psq<S: EvalScore>(board: &Board, color: Color, params: &Params<S>) -> S {
+    let mut score = S::default();
+    for piece in Piece::iter() {
+        for sq in board.get_color_piece(color, piece) {
+            // Tables have the 8th rank first, so the white squares are flipped.
+            let sq = color.mirror_square(sq) as usize ^ 56;
+            score +=
+                params.material[piece as usize].clone() + params.pst[piece as usize][sq].clone();
+        }
+    }
+    score
+}
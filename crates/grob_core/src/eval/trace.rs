@@ -0,0 +1,123 @@
+//! Breakdown of the static evaluation into its terms.
+
+use std::fmt;
+
+use strum::{Display, EnumCount, EnumIter, IntoEnumIterator};
+
+use crate::{
+    eval::{TaperedScore, MAX_PHASE},
+    Color, Score,
+};
+
+/// A term of the static evaluation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumCount, EnumIter, Display)]
+pub enum Term {
+    /// Values of the pieces.
+    Material,
+    /// Bonuses for the placement of the pieces ([piece-square tables]).
+    ///
+    /// [piece-square tables]: https://www.chessprogramming.org/Piece-Square_Tables
+    #[strum(serialize = "PST")]
+    Pst,
+    /// Pawn structure, including the passed pawns.
+    Pawns,
+    /// Safety of the king.
+    #[strum(serialize = "King safety")]
+    KingSafety,
+    /// Number of squares the pieces can move to.
+    Mobility,
+    /// Bonus for the player who is about to make a turn.
+    Tempo,
+    /// Output of the NNUE network, which replaces all the other terms
+    /// when the `nnue` feature is used.
+    Network,
+}
+
+/// Scores of all evaluation [`Term`]s for both players and both phases.
+///
+/// See [`Evaluator::trace`](crate::eval::Evaluator::trace).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Trace {
+    terms: [[TaperedScore; Color::COUNT]; Term::COUNT],
+    phase: i32,
+    turn: Color,
+}
+
+impl Trace {
+    /// Creates a new [`Trace`] with all terms set to zero.
+    pub fn new(phase: i32, turn: Color) -> Self {
+        Self {
+            terms: [[TaperedScore::ZERO; Color::COUNT]; Term::COUNT],
+            phase,
+            turn,
+        }
+    }
+    /// Returns the score of the term for the player.
+    pub fn get(&self, term: Term, color: Color) -> TaperedScore {
+        self.terms[term as usize][color as usize]
+    }
+    /// Sets the score of the term for the player.
+    pub fn set(&mut self, term: Term, color: Color, score: TaperedScore) {
+        self.terms[term as usize][color as usize] = score;
+    }
+    /// Returns the game phase the scores are tapered with.
+    pub fn phase(&self) -> i32 {
+        self.phase
+    }
+    /// Returns the sum of the term for both players
+    /// from the white player's point of view.
+    pub fn term(&self, term: Term) -> TaperedScore {
+        self.get(term, Color::White) - self.get(term, Color::Black)
+    }
+    /// Returns the sum of all terms from the white player's point of view.
+    pub fn total(&self) -> TaperedScore {
+        Term::iter().fold(TaperedScore::ZERO, |total, term| total + self.term(term))
+    }
+    /// Returns the final evaluation for the player who is about to make a turn.
+    pub fn score(&self) -> Score {
+        let score = self.total().taper(self.phase);
+        match self.turn {
+            Color::White => Score::Cp(score),
+            Color::Black => Score::Cp(-score),
+        }
+    }
+}
+
+impl fmt::Display for Trace {
+    /// Formats the trace as a table in centi-pawns.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const SEPARATOR: &str = "-------------+-------------+-------------+-------------";
+        let pair = |score: TaperedScore| format!("{:>5} {:>5}", score.mg, score.eg);
+
+        writeln!(
+            f,
+            "{:>12} | {:^11} | {:^11} | {:^11}",
+            "Term", "White", "Black", "Total"
+        )?;
+        writeln!(f, "{:>12} | {1} | {1} | {1}", "", "   MG    EG")?;
+        writeln!(f, "{SEPARATOR}")?;
+        for term in Term::iter() {
+            writeln!(
+                f,
+                "{:>12} | {} | {} | {}",
+                term.to_string(),
+                pair(self.get(term, Color::White)),
+                pair(self.get(term, Color::Black)),
+                pair(self.term(term)),
+            )?;
+        }
+        writeln!(f, "{SEPARATOR}")?;
+        writeln!(
+            f,
+            "{:>12} | {:11} | {:11} | {}",
+            "Total",
+            "",
+            "",
+            pair(self.total())
+        )?;
+        writeln!(f)?;
+        writeln!(f, "Phase: {}/{MAX_PHASE}", self.phase)?;
+        let white_score = self.total().taper(self.phase);
+        write!(f, "Evaluation: {white_score} (white side)")
+    }
+}
@@ -4,6 +4,7 @@ mod base;
 mod lan;
 mod make;
 mod movegen;
+mod polyglot;
 mod zobrist;
 
 pub use base::{Game, ParseFenError};
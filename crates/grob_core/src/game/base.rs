@@ -1,6 +1,13 @@
+#[cfg(feature = "nnue")]
+use std::sync::Arc;
 use std::{collections::VecDeque, error::Error, fmt, num::NonZeroU64};
 
+use strum::IntoEnumIterator;
+
+#[cfg(feature = "nnue")]
+use crate::eval::nnue::{Accumulator, Network};
 use crate::{
+    eval::{phase_weight, square_score, TaperedScore},
     game::{
         make::ChessUnmove,
         zobrist::{
@@ -45,6 +52,11 @@ pub struct Game {
     board: Board,
     history: Vec<PlyHistory>,
     zobrist_hash: u64,
+    pawn_zobrist_hash: u64,
+    psq: TaperedScore,
+    phase: i32,
+    #[cfg(feature = "nnue")]
+    nnue: Option<Accumulator>,
     move_index_rule_50: u32,
     move_index: u32,
     turn: Color,
@@ -67,8 +79,6 @@ impl Game {
     /// Tries to parse a positioin from FEN.
     pub fn try_from_fen(fen: &str) -> Result<Self, ParseFenError> {
         let mut words: VecDeque<&str> = fen.split_whitespace().collect();
-        let mut zobrist_hash = 0;
-
         let board = {
             let fen = words.pop_front().ok_or(ParseFenError::BadBoard)?;
             let rows: Vec<&str> = fen.split('/').collect();
@@ -97,7 +107,6 @@ impl Game {
                         };
 
                         board.mask_or(color, piece, BitBoard::from(sq));
-                        zobrist_hash ^= get_square_zobrist(color, piece, sq);
                     }
                     sq = sq.shifted(1);
                     row_len += 1;
@@ -124,7 +133,6 @@ impl Game {
                 .pop_front()
                 .and_then(|s| s.parse::<Color>().ok())
                 .ok_or(ParseFenError::BadTurn)?;
-            zobrist_hash ^= get_turn_zobrist(turn);
 
             if board.is_king_in_check(!turn) {
                 return Err(ParseFenError::BadBoard);
@@ -168,13 +176,12 @@ impl Game {
             if !castling_rights_max.contains(castling_rights) {
                 return Err(ParseFenError::BadCastlingRights);
             }
-            zobrist_hash ^= get_castling_zobrist(castling_rights);
             castling_rights
         };
 
         let en_passant = {
             let fen = words.pop_front().ok_or(ParseFenError::BadEnPassant)?;
-            let en_passant = match fen {
+            match fen {
                 "-" => None,
                 s => {
                     let file = s.parse::<File>().map_err(|_| ParseFenError::BadEnPassant)?;
@@ -186,9 +193,7 @@ impl Game {
                     }
                     Some(file)
                 }
-            };
-            zobrist_hash ^= get_en_passant_zobrist(en_passant);
-            en_passant
+            }
         };
 
         let hm = words
@@ -219,22 +224,112 @@ impl Game {
 
         let history = Vec::new();
 
-        Ok(Game {
+        let mut game = Game {
             board,
             turn,
             castling_rights,
             en_passant,
             move_index,
             move_index_rule_50,
-            zobrist_hash,
+            zobrist_hash: 0,
+            pawn_zobrist_hash: 0,
+            psq: TaperedScore::ZERO,
+            phase: 0,
+            #[cfg(feature = "nnue")]
+            nnue: None,
             history,
-        })
+        };
+        game.zobrist_hash = game.compute_zobrist_hash();
+        game.pawn_zobrist_hash = game.compute_pawn_zobrist();
+        game.psq = game.compute_psq();
+        game.phase = game.compute_phase();
+        Ok(game)
     }
     /// Returns a hash for the current position.
     #[must_use]
     pub fn zobrist(&self) -> NonZeroU64 {
         NonZeroU64::new(self.zobrist_hash).unwrap_or(NonZeroU64::MAX)
     }
+    /// Computes the hash for the current position from scratch.
+    ///
+    /// Unlike [`Self::zobrist`], which is updated incrementally as the moves
+    /// are made, this is slow and is meant for verifying the former.
+    #[must_use]
+    pub fn compute_zobrist(&self) -> NonZeroU64 {
+        NonZeroU64::new(self.compute_zobrist_hash()).unwrap_or(NonZeroU64::MAX)
+    }
+    /// Returns a hash of the pawns' placement.
+    ///
+    /// Unlike [`Self::zobrist`], this is zero if there are no pawns.
+    #[must_use]
+    pub fn pawn_zobrist(&self) -> u64 {
+        self.pawn_zobrist_hash
+    }
+    /// Computes [`Self::pawn_zobrist`] from scratch.
+    ///
+    /// This is slow and is meant for verifying the incremental updates.
+    #[must_use]
+    pub fn compute_pawn_zobrist(&self) -> u64 {
+        let mut hash = 0;
+        for color in Color::iter() {
+            for sq in self.board.get_color_piece(color, Piece::Pawn) {
+                hash ^= get_square_zobrist(color, Piece::Pawn, sq);
+            }
+        }
+        hash
+    }
+    /// Returns the sum of material and piece-square table scores
+    /// from the white player's point of view.
+    #[must_use]
+    pub fn psq(&self) -> TaperedScore {
+        self.psq
+    }
+    /// Computes [`Self::psq`] from scratch.
+    ///
+    /// This is slow and is meant for verifying the incremental updates.
+    #[must_use]
+    pub fn compute_psq(&self) -> TaperedScore {
+        let mut psq = TaperedScore::ZERO;
+        for color in Color::iter() {
+            for piece in Piece::iter() {
+                for sq in self.board.get_color_piece(color, piece) {
+                    psq += square_score(color, piece, sq);
+                }
+            }
+        }
+        psq
+    }
+    /// Returns the game phase, which is [`MAX_PHASE`] in the opening
+    /// and drops towards zero as the pieces are exchanged.
+    ///
+    /// [`MAX_PHASE`]: crate::eval::MAX_PHASE
+    #[must_use]
+    pub fn phase(&self) -> i32 {
+        self.phase
+    }
+    /// Computes [`Self::phase`] from scratch.
+    ///
+    /// This is slow and is meant for verifying the incremental updates.
+    #[must_use]
+    pub fn compute_phase(&self) -> i32 {
+        Piece::iter()
+            .map(|piece| phase_weight(piece) * self.board.get_piece(piece).count() as i32)
+            .sum()
+    }
+    /// Sets the NNUE network that evaluates the position or removes it.
+    ///
+    /// The hidden layer of the network is then updated incrementally
+    /// as the moves are made.
+    #[cfg(feature = "nnue")]
+    pub fn set_network(&mut self, network: Option<Arc<Network>>) {
+        self.nnue = network.map(|network| Accumulator::new(network, &self.board));
+    }
+    /// Returns the hidden layer of the NNUE network if it is set.
+    #[cfg(feature = "nnue")]
+    #[must_use]
+    pub fn nnue(&self) -> Option<&Accumulator> {
+        self.nnue.as_ref()
+    }
     /// Returns the possible en passant target file if available or `None`.
     #[must_use]
     pub fn en_passant(&self) -> Option<File> {
@@ -267,13 +362,71 @@ impl Game {
     }
     /// Returns the number of times this position was played before in the game.
     pub fn count_repetitions(&self) -> usize {
+        self.repetitions().count()
+    }
+    /// Returns the number of plies since this position was last played,
+    /// or `None` if it was not played before in the game.
+    pub fn last_repetition(&self) -> Option<usize> {
+        self.repetitions().next()
+    }
+    /// Returns the number of plies since each of the earlier occurrences
+    /// of this position, starting with the latest one.
+    fn repetitions(&self) -> impl Iterator<Item = usize> + '_ {
         let hash = self.zobrist();
-        self.history.iter().filter(|&ply| ply.hash == hash).count()
+        // Positions before the last capture or pawn move can not repeat,
+        // and neither can the ones with the other player to move.
+        self.history
+            .iter()
+            .rev()
+            .take(self.halfmove_clock() as usize)
+            .enumerate()
+            .skip(1)
+            .step_by(2)
+            .filter(move |(_, ply)| ply.hash == hash)
+            .map(|(index, _)| index + 1)
+    }
+    /// Returns `true` if neither player can possibly checkmate.
+    ///
+    /// This is the case when there are only kings and either at most one
+    /// minor piece or only bishops that are all on squares of the same color.
+    pub fn is_insufficient_material(&self) -> bool {
+        const DARK_SQUARES: BitBoard = BitBoard(0xAA55_AA55_AA55_AA55);
+        let board = self.board();
+        let pawns_and_majors = board.get_piece(Piece::Pawn)
+            | board.get_piece(Piece::Rook)
+            | board.get_piece(Piece::Queen);
+        if !pawns_and_majors.is_empty() {
+            return false;
+        }
+
+        let knights = board.get_piece(Piece::Knight);
+        let bishops = board.get_piece(Piece::Bishop);
+        if (knights | bishops).count() <= 1 {
+            return true;
+        }
+        knights.is_empty()
+            && ((bishops & DARK_SQUARES).is_empty() || (bishops & !DARK_SQUARES).is_empty())
     }
     /// Returns `true` if there are no moves to roll back.
     pub fn is_history_empty(&self) -> bool {
         self.history.is_empty()
     }
+    /// Returns the piece captured by the last played move or `None`
+    /// if it was not a capture.
+    ///
+    /// The pawn captured en passant is not reported, since it
+    /// was not standing on the destination square.
+    pub fn last_capture(&self) -> Option<Piece> {
+        self.history.last().and_then(|ply| ply.unmove.capture)
+    }
+    /// Returns `true` if the last played move is a null move.
+    ///
+    /// See [`Self::make_null_move`].
+    pub fn was_null_move(&self) -> bool {
+        self.history
+            .last()
+            .is_some_and(|ply| ply.unmove.chess_move.is_none())
+    }
     /// Returns `true` if the king of the playing player is currently in check.
     pub fn is_check(&self) -> bool {
         self.board().is_king_in_check(self.turn())
@@ -282,6 +435,21 @@ impl Game {
     pub(super) fn was_check_ignored(&self) -> bool {
         self.board().is_king_in_check(!self.turn())
     }
+    /// Computes the raw zobrist hash from all parts of the position.
+    fn compute_zobrist_hash(&self) -> u64 {
+        let mut hash = 0;
+        for color in Color::iter() {
+            for piece in Piece::iter() {
+                for sq in self.board.get_color_piece(color, piece) {
+                    hash ^= get_square_zobrist(color, piece, sq);
+                }
+            }
+        }
+        hash ^= get_turn_zobrist(self.turn);
+        hash ^= get_castling_zobrist(self.castling_rights);
+        hash ^= get_en_passant_zobrist(self.en_passant);
+        hash
+    }
     /// Sets the currently available en passant file.
     ///
     /// This will update the zobrist hash.
@@ -300,7 +468,7 @@ impl Game {
     }
     /// Adds a piece to the board.
     ///
-    /// This will update the zobrist hash.
+    /// This will update the zobrist hashes and the evaluation terms.
     ///
     /// # Panics
     /// If trying to add the piece to an already occupied square
@@ -308,11 +476,20 @@ impl Game {
     pub(super) fn add_color_piece(&mut self, color: Color, piece: Piece, sq: Square) {
         debug_assert!(!self.board.get_occupance().has_square(sq));
         self.zobrist_hash ^= get_square_zobrist(color, piece, sq);
+        if piece == Piece::Pawn {
+            self.pawn_zobrist_hash ^= get_square_zobrist(color, piece, sq);
+        }
+        self.psq += square_score(color, piece, sq);
+        self.phase += phase_weight(piece);
         self.board.mask_or(color, piece, BitBoard::from(sq));
+        #[cfg(feature = "nnue")]
+        if let Some(nnue) = &mut self.nnue {
+            nnue.add(&self.board, color, piece, sq);
+        }
     }
     /// Removes a piece from the board.
     ///
-    /// This will update the zobrist hash.
+    /// This will update the zobrist hashes and the evaluation terms.
     ///
     /// # Panics
     /// - if trying to remove an unoccupied square
@@ -324,11 +501,20 @@ impl Game {
                 && self.board.get_color(color).has_square(sq)
         );
         self.zobrist_hash ^= get_square_zobrist(color, piece, sq);
+        if piece == Piece::Pawn {
+            self.pawn_zobrist_hash ^= get_square_zobrist(color, piece, sq);
+        }
+        self.psq -= square_score(color, piece, sq);
+        self.phase -= phase_weight(piece);
         self.board.mask_and(color, piece, !BitBoard::from(sq));
+        #[cfg(feature = "nnue")]
+        if let Some(nnue) = &mut self.nnue {
+            nnue.remove(&self.board, color, piece, sq);
+        }
     }
     /// Moves a piece on the board.
     ///
-    /// This will update the zobrist hash.
+    /// This will update the zobrist hashes and the evaluation terms.
     ///
     /// # Panics
     /// - if trying to move a piece from an unoccupied square
@@ -349,8 +535,17 @@ impl Game {
         debug_assert!(!self.board.get_occupance().has_square(to));
         self.zobrist_hash ^= get_square_zobrist(color, piece, from);
         self.zobrist_hash ^= get_square_zobrist(color, piece, to);
+        if piece == Piece::Pawn {
+            self.pawn_zobrist_hash ^=
+                get_square_zobrist(color, piece, from) ^ get_square_zobrist(color, piece, to);
+        }
+        self.psq += square_score(color, piece, to) - square_score(color, piece, from);
         self.board
             .mask_xor(color, piece, BitBoard::from(from) | BitBoard::from(to));
+        #[cfg(feature = "nnue")]
+        if let Some(nnue) = &mut self.nnue {
+            nnue.move_piece(&self.board, color, piece, from, to);
+        }
     }
     /// Changes the color of side to move.
     ///
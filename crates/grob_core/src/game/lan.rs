@@ -6,6 +6,14 @@ use crate::{game::Game, ChessMove, Piece, Promotion, Square};
 ///
 /// [LAN (Long Algebraic Notation)]:
 /// https://www.chessprogramming.org/Algebraic_Chess_Notation#Long_Algebraic_Notation_.28LAN.29
+///
+/// # Examples
+/// ```rust
+/// use grob_core::LanMove;
+///
+/// let lan_move: LanMove = "E7e8Q".parse().unwrap();
+/// assert_eq!(lan_move.to_string(), "e7e8q");
+/// ```
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct LanMove {
     /// The origin square.
@@ -38,7 +46,10 @@ impl FromStr for LanMove {
 
 impl fmt::Display for LanMove {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}{}", self.from, self.to)?;
+        // UCI expects the squares in lowercase.
+        let from = self.from.to_string().to_ascii_lowercase();
+        let to = self.to.to_string().to_ascii_lowercase();
+        write!(f, "{from}{to}")?;
         if let Some(promotion) = self.promotion {
             write!(f, "{promotion}")?;
         }
@@ -74,6 +85,7 @@ impl Game {
                     // "to" is unoccupied for en passant.
                     self.push_pawn_attacks(&mut test_move);
                     self.push_pawn_quiets(&mut test_move);
+                    self.push_pawn_promotions(&mut test_move);
                 }
                 Piece::Bishop => self.push_bishop_quiets(&mut test_move),
                 Piece::Knight => self.push_knight_quiets(&mut test_move),
@@ -6,10 +6,11 @@ use crate::{
 /// Data needed to rollback a move.
 #[derive(Debug, Clone, Copy)]
 pub(super) struct ChessUnmove {
-    chess_move: ChessMove,
+    /// The played move or `None` for a null move.
+    pub(super) chess_move: Option<ChessMove>,
     // Capture does not need to be wrapped, but it's safer.
     // Could maybe remove if it'll be bad for performance.
-    capture: Option<Piece>,
+    pub(super) capture: Option<Piece>,
     en_passant: Option<File>,
     castling_rights: CastlingRights,
     halfmove_clock: u32,
@@ -181,7 +182,7 @@ impl Game {
         let halfmove_clock = self.next_move_index(reset_hm_clock);
 
         let unmove = ChessUnmove {
-            chess_move,
+            chess_move: Some(chess_move),
             capture,
             en_passant,
             castling_rights,
@@ -197,15 +198,49 @@ impl Game {
 
         is_legal
     }
-    /// Rolls back the last played move.
+    /// Passes the turn to the other player without moving any pieces.
+    ///
+    /// Null moves are not allowed by the rules, but the search uses them
+    /// to find out whether the position is good even without making a move.
+    /// Positions played before the null move are not counted as repetitions
+    /// of the positions after it.
+    ///
+    /// # Panics
+    /// Panics if the king of the playing player is in check.
+    pub fn make_null_move(&mut self) {
+        assert!(!self.is_check(), "Null move is not allowed in check!");
+        let hash = self.zobrist();
+        let en_passant = self.en_passant();
+        self.set_en_passant(None);
+        self.swap_turn();
+        let halfmove_clock = self.next_move_index(true);
+
+        let unmove = ChessUnmove {
+            chess_move: None,
+            capture: None,
+            en_passant,
+            castling_rights: self.castling_rights(),
+            halfmove_clock,
+        };
+        self.push_history(PlyHistory { hash, unmove });
+    }
+    /// Rolls back the last null move.
+    ///
+    /// # Panics
+    /// Panics if the last played move is not a null move.
+    pub fn unmake_null_move(&mut self) {
+        assert!(self.was_null_move(), "The last move is not a null move!");
+        self.unmake_move();
+    }
+    /// Rolls back the last played move (or null move).
     ///
     /// # Panics
     /// Panics if there are no moves to roll back.
     pub fn unmake_move(&mut self) {
         assert!(self.try_unmake_move(), "There are no moves to unmake!")
     }
-    /// Rolls back the last played move and returns `true` or
-    /// returns `false` if there are no moves to roll back.
+    /// Rolls back the last played move (or null move) and returns `true`
+    /// or returns `false` if there are no moves to roll back.
     #[must_use]
     pub fn try_unmake_move(&mut self) -> bool {
         let Some(ply_history) = self.pop_history() else {
@@ -217,9 +252,12 @@ impl Game {
         self.set_en_passant(chess_unmove.en_passant);
         self.prev_move_index(chess_unmove.halfmove_clock);
 
-        let from = chess_unmove.chess_move.orig_square();
-        let to = chess_unmove.chess_move.dest_square();
-        let hint = chess_unmove.chess_move.hint();
+        let Some(chess_move) = chess_unmove.chess_move else {
+            return true;
+        };
+        let from = chess_move.orig_square();
+        let to = chess_move.dest_square();
+        let hint = chess_move.hint();
 
         let piece = self.board().get_piece_at(to).unwrap();
         let capture = chess_unmove.capture;
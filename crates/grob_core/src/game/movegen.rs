@@ -125,14 +125,32 @@ impl PackedChessMove {
         let hint = ChessMoveHint::from_repr(((self.data >> 12) & 0x0f) as u8).unwrap();
         ChessMove { to, from, hint }
     }
+    /// Returns the raw bits of the compact form.
+    #[inline(always)]
+    #[must_use]
+    pub fn to_bits(self) -> u16 {
+        self.data
+    }
+    /// Restores the compact form from the raw bits or returns `None`
+    /// if the bits do not represent a valid move.
+    #[inline(always)]
+    #[must_use]
+    pub fn from_bits(bits: u16) -> Option<Self> {
+        ChessMoveHint::from_repr(((bits >> 12) & 0x0f) as u8)?;
+        Some(Self { data: bits })
+    }
 }
 
 impl Game {
     /// Generate pseudo-legal moves from this position.
     pub fn push_moves(&self, push_move: &mut impl FnMut(ChessMove)) {
+        self.push_captures(push_move);
+        self.push_quiets(push_move);
+    }
+    /// Generate pseudo-legal captures and promotions from this position.
+    pub fn push_captures(&self, push_move: &mut impl FnMut(ChessMove)) {
         if self.board().get_king_checkers(self.turn()).count() >= 2 {
             self.push_king_attacks(push_move);
-            self.push_king_quiets(push_move);
             return;
         }
 
@@ -141,6 +159,14 @@ impl Game {
         self.push_bishop_attacks(push_move);
         self.push_rook_attacks(push_move);
         self.push_king_attacks(push_move);
+        self.push_pawn_promotions(push_move);
+    }
+    /// Generate pseudo-legal quiet moves (except for promotions) from this position.
+    pub fn push_quiets(&self, push_move: &mut impl FnMut(ChessMove)) {
+        if self.board().get_king_checkers(self.turn()).count() >= 2 {
+            self.push_king_quiets(push_move);
+            return;
+        }
 
         self.push_castlings(push_move);
         self.push_king_quiets(push_move);
@@ -157,7 +183,7 @@ impl Game {
             .bit_scan_forward()
             .unwrap();
         let attacks = BitBoard::king_attacks(from);
-        self.push_quiets(push_move, attacks, from, ChessMoveHint::Quiet);
+        self.push_quiets_from(push_move, attacks, from, ChessMoveHint::Quiet);
     }
     /// Generate pseudo-legal king attacks from this position.
     pub fn push_king_attacks(&self, push_move: &mut impl FnMut(ChessMove)) {
@@ -167,20 +193,20 @@ impl Game {
             .bit_scan_forward()
             .unwrap();
         let attacks = BitBoard::king_attacks(from);
-        self.push_attacks(push_move, attacks, from, ChessMoveHint::Caputre);
+        self.push_attacks_from(push_move, attacks, from, ChessMoveHint::Caputre);
     }
     /// Generate pseudo-legal knight's quiet moves from this position.
     pub fn push_knight_quiets(&self, push_move: &mut impl FnMut(ChessMove)) {
         for from in self.board().get_color_piece(self.turn(), Piece::Knight) {
             let attacks = BitBoard::knight_attacks(from);
-            self.push_quiets(push_move, attacks, from, ChessMoveHint::Quiet);
+            self.push_quiets_from(push_move, attacks, from, ChessMoveHint::Quiet);
         }
     }
     /// Generate pseudo-legal knight attacks from this position.
     pub fn push_knight_attacks(&self, push_move: &mut impl FnMut(ChessMove)) {
         for from in self.board().get_color_piece(self.turn(), Piece::Knight) {
             let attacks = BitBoard::knight_attacks(from);
-            self.push_attacks(push_move, attacks, from, ChessMoveHint::Caputre);
+            self.push_attacks_from(push_move, attacks, from, ChessMoveHint::Caputre);
         }
     }
     /// Generate pseudo-legal bishop-like quiet moves from this position.
@@ -189,7 +215,7 @@ impl Game {
 
         for from in self.board().get_color_bishop_sliders(self.turn()) {
             let attacks = BitBoard::bishop_attacks(occ, from);
-            self.push_quiets(push_move, attacks, from, ChessMoveHint::Quiet);
+            self.push_quiets_from(push_move, attacks, from, ChessMoveHint::Quiet);
         }
     }
     /// Generate pseudo-legal bishop-like attacks from this position.
@@ -198,7 +224,7 @@ impl Game {
 
         for from in self.board().get_color_bishop_sliders(self.turn()) {
             let attacks = BitBoard::bishop_attacks(occ, from);
-            self.push_attacks(push_move, attacks, from, ChessMoveHint::Caputre);
+            self.push_attacks_from(push_move, attacks, from, ChessMoveHint::Caputre);
         }
     }
     /// Generate pseudo-legal rook-like quiet moves from this position.
@@ -207,7 +233,7 @@ impl Game {
 
         for from in self.board().get_color_rook_sliders(self.turn()) {
             let attacks = BitBoard::rook_attacks(occ, from);
-            self.push_quiets(push_move, attacks, from, ChessMoveHint::Quiet);
+            self.push_quiets_from(push_move, attacks, from, ChessMoveHint::Quiet);
         }
     }
     /// Generate pseudo-legal rook-like attacks from this position.
@@ -216,10 +242,10 @@ impl Game {
 
         for from in self.board().get_color_rook_sliders(self.turn()) {
             let attacks = BitBoard::rook_attacks(occ, from);
-            self.push_attacks(push_move, attacks, from, ChessMoveHint::Caputre);
+            self.push_attacks_from(push_move, attacks, from, ChessMoveHint::Caputre);
         }
     }
-    /// Generate pseudo-legal quiet pawn moves from this position.
+    /// Generate pseudo-legal quiet pawn moves (except for promotions) from this position.
     pub fn push_pawn_quiets(&self, push_move: &mut impl FnMut(ChessMove)) {
         let empty = !self.board().get_occupance();
 
@@ -237,12 +263,7 @@ impl Game {
             empty,
             self.turn(),
         );
-        let promotion_pushes = single_pushes
-            & match self.turn() {
-                Color::White => BitBoard::from_rank(Rank::R8),
-                Color::Black => BitBoard::from_rank(Rank::R1),
-            };
-        let quiet_pushes = single_pushes & !promotion_pushes;
+        let quiet_pushes = single_pushes & !BitBoard::from_rank(self.turn().mirror_rank(Rank::R8));
         let push_offset = if self.turn() == Color::White { -8 } else { 8 };
 
         Self::push_for_each_shifted(push_move, quiet_pushes, push_offset, ChessMoveHint::Quiet);
@@ -252,6 +273,18 @@ impl Game {
             push_offset * 2,
             ChessMoveHint::DoublePawn,
         );
+    }
+    /// Generate pseudo-legal quiet pawn promotions from this position.
+    pub fn push_pawn_promotions(&self, push_move: &mut impl FnMut(ChessMove)) {
+        let empty = !self.board().get_occupance();
+
+        let promotion_pushes = BitBoard::pawn_pushes(
+            self.board().get_color_piece(self.turn(), Piece::Pawn),
+            empty,
+            self.turn(),
+        ) & BitBoard::from_rank(self.turn().mirror_rank(Rank::R8));
+        let push_offset = if self.turn() == Color::White { -8 } else { 8 };
+
         Self::push_for_each_shifted(
             push_move,
             promotion_pushes,
@@ -286,25 +319,25 @@ impl Game {
         let pawns = pawns & !promoters;
         for from in promoters {
             let attacks = BitBoard::pawn_attacks(from, self.turn()) & opp;
-            self.push_attacks(
+            self.push_attacks_from(
                 push_move,
                 attacks,
                 from,
                 ChessMoveHint::BishopPromotionCapture,
             );
-            self.push_attacks(
+            self.push_attacks_from(
                 push_move,
                 attacks,
                 from,
                 ChessMoveHint::KnightPromotionCapture,
             );
-            self.push_attacks(
+            self.push_attacks_from(
                 push_move,
                 attacks,
                 from,
                 ChessMoveHint::RookPromotionCapture,
             );
-            self.push_attacks(
+            self.push_attacks_from(
                 push_move,
                 attacks,
                 from,
@@ -328,7 +361,7 @@ impl Game {
 
         for from in pawns {
             let attacks = BitBoard::pawn_attacks(from, self.turn()) & opp;
-            self.push_attacks(push_move, attacks, from, ChessMoveHint::Caputre);
+            self.push_attacks_from(push_move, attacks, from, ChessMoveHint::Caputre);
         }
     }
     /// Generate pseudo-legal castling moves from this position.
@@ -352,7 +385,7 @@ impl Game {
             });
         }
     }
-    fn push_attacks(
+    fn push_attacks_from(
         &self,
         push_move: &mut impl FnMut(ChessMove),
         attacks: BitBoard,
@@ -392,7 +425,7 @@ impl Game {
             hint,
         );
     }
-    fn push_quiets(
+    fn push_quiets_from(
         &self,
         push_move: &mut impl FnMut(ChessMove),
         attacks: BitBoard,
@@ -496,6 +529,10 @@ impl Game {
                     if piece != Piece::Pawn {
                         return false;
                     }
+                    if to.rank() == color.mirror_rank(Rank::R8) {
+                        // Pawns must promote when reaching the last rank.
+                        return false;
+                    }
                     if hint.is_capture() {
                         !(BitBoard::pawn_attacks(from, color) & BitBoard::from(to)).is_empty()
                     } else {
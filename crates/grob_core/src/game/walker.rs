@@ -2,7 +2,7 @@ use either::Either;
 
 mod move_list;
 
-use crate::{ChessMove, Game};
+use crate::{ChessMove, ChessMoveHint, Game, Piece};
 use move_list::MoveList;
 
 /// Possible ending for a chess game.
@@ -12,9 +12,26 @@ pub enum GameEnding {
     Stalemate,
     /// One of the players wins by checkmate.
     Checkmate,
+    /// Position was repeated too many times (draw).
+    Repetition,
+    /// No captures or pawn moves were made in the last fifty moves (draw).
+    FiftyMoves,
+    /// Neither player has enough material to checkmate (draw).
+    InsufficientMaterial,
+}
+
+impl GameEnding {
+    /// Returns `true` if the game ends in a draw.
+    pub fn is_draw(self) -> bool {
+        self != GameEnding::Checkmate
+    }
 }
 
 /// Specifies the move ordering policy.
+///
+/// Moves are always generated in stages: captures and promotions go
+/// first and the quiet moves are only generated if none of the earlier
+/// moves called [`GameTreeWalker::exhaust_moves`].
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum MoveOrdering {
     /// Order captrues before the quiet moves.
@@ -25,6 +42,53 @@ pub enum MoveOrdering {
     ///
     /// [MVV-LVA]: https://www.chessprogramming.org/MVV-LVA
     MvvLva,
+    /// Same as [`MoveOrdering::MvvLva`] but also try the hash move before
+    /// the captures and the [killer moves] before the rest of the quiet moves.
+    ///
+    /// Moves that are not pseudo-legal in the position are ignored.
+    ///
+    /// [killer moves]: https://www.chessprogramming.org/Killer_Heuristic
+    Staged {
+        /// Best move found by the previous search of the position.
+        hash_move: Option<ChessMove>,
+        /// Quiet moves that caused a cutoff in the sibling positions.
+        killers: [Option<ChessMove>; 2],
+    },
+}
+
+impl MoveOrdering {
+    fn hash_move(self) -> Option<ChessMove> {
+        match self {
+            MoveOrdering::Staged { hash_move, .. } => hash_move,
+            _ => None,
+        }
+    }
+    fn killers(self) -> [Option<ChessMove>; 2] {
+        match self {
+            MoveOrdering::Staged { killers, .. } => killers,
+            _ => [None; 2],
+        }
+    }
+}
+
+/// Specifies which moves should be generated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MoveKind {
+    /// All of the moves.
+    All,
+    /// Only captures and promotions.
+    Captures,
+    /// Only quiet moves (except for promotions).
+    Quiets,
+}
+
+impl MoveKind {
+    fn has_captures(self) -> bool {
+        matches!(self, MoveKind::All | MoveKind::Captures)
+    }
+    fn has_quiets(self) -> bool {
+        matches!(self, MoveKind::All | MoveKind::Quiets)
+    }
 }
 
 /// Expores through [`Game`]'s moves.
@@ -58,18 +122,54 @@ impl GameTreeWalker<'_> {
         self.game
     }
     /// Returns any legal move for this position or a [`GameEnding`]
-    /// if the position has no legal moves.
+    /// if the game is over.
+    ///
+    /// Repetition ends the game once the position occurs for the third time.
     pub fn check_ending(&mut self) -> Either<ChessMove, GameEnding> {
         let mut any_move = None;
         let ending = self.for_each_legal_child_node(MoveOrdering::default(), |node, chess_move| {
             any_move = Some(chess_move);
             node.exhaust_moves();
         });
-        match ending {
+        match ending.or_else(|| self.check_draw(0)) {
             Some(ending) => Either::Right(ending),
             None => Either::Left(any_move.unwrap()),
         }
     }
+    /// Returns a [`GameEnding`] if the position is a draw by the fifty-move rule,
+    /// repetition or insufficient material.
+    ///
+    /// Repetition is detected once the position occurs for the third time,
+    /// or for the second time if it first occurred at most `ply` plies ago
+    /// (in the search tree of a position `ply` plies back).
+    ///
+    /// Positions without legal moves are not detected, except that
+    /// a checkmate is never considered a draw by the fifty-move rule.
+    pub fn check_draw(&mut self, ply: usize) -> Option<GameEnding> {
+        if self.game.halfmove_clock() >= 100 {
+            let is_checkmate = self.game.is_check()
+                && self
+                    .for_each_legal_child_node(MoveOrdering::default(), |node, _| {
+                        node.exhaust_moves();
+                    })
+                    .is_some();
+            if !is_checkmate {
+                return Some(GameEnding::FiftyMoves);
+            }
+        }
+        if self.game.count_repetitions() >= 2
+            || self
+                .game
+                .last_repetition()
+                .is_some_and(|plies| plies <= ply)
+        {
+            return Some(GameEnding::Repetition);
+        }
+        if self.game.is_insufficient_material() {
+            return Some(GameEnding::InsufficientMaterial);
+        }
+        None
+    }
     /// Makes a move and inspects the resulting state of the game
     /// with a function if the move is legal.
     /// Returns `true` if the move was legal.
@@ -85,32 +185,33 @@ impl GameTreeWalker<'_> {
         self.game.unmake_move();
         true
     }
+    /// Makes a null move and inspects the resulting state of the game
+    /// with a function (see [`Game::make_null_move`]).
+    ///
+    /// # Panics
+    /// Panics if the king of the playing player is in check.
+    #[inline(always)]
+    pub fn map_null_move<F, R>(&mut self, op: F) -> R
+    where
+        F: FnOnce(&mut Self) -> R,
+    {
+        self.game.make_null_move();
+        let res = op(self);
+        self.game.unmake_null_move();
+        res
+    }
     /// Inspects all legal moves in position with a function.
     /// Returns `Some(game_ending: GameEnding)` if there are no legal moves.
     #[inline(always)]
     pub fn for_each_legal_child_node<F>(
         &mut self,
         policy: MoveOrdering,
-        mut op: F,
+        op: F,
     ) -> Option<GameEnding>
     where
         F: FnMut(&mut Self, ChessMove),
     {
-        self.move_list.push_group();
-        self.generate_moves(policy);
-
-        let mut has_moves = false;
-        while let Some(chess_move) = self.move_list.pop_move() {
-            if self.game.make_move_unchecked(chess_move) {
-                has_moves = true;
-                op(self, chess_move);
-                self.game.unmake_move();
-            }
-        }
-
-        self.move_list.pop_group();
-
-        if has_moves {
+        if self.for_each_legal_node_of(MoveKind::All, policy, op) {
             None
         } else if self.game.is_check() {
             Some(GameEnding::Checkmate)
@@ -118,33 +219,141 @@ impl GameTreeWalker<'_> {
             Some(GameEnding::Stalemate)
         }
     }
+    /// Inspects all legal captures and promotions in position with a function.
+    /// Returns `false` if there are no such moves.
+    ///
+    /// Unlike [`Self::for_each_legal_child_node`] this cannot detect a [`GameEnding`],
+    /// since quiet moves are never generated.
+    #[inline(always)]
+    pub fn for_each_legal_capture_node<F>(&mut self, policy: MoveOrdering, op: F) -> bool
+    where
+        F: FnMut(&mut Self, ChessMove),
+    {
+        self.for_each_legal_node_of(MoveKind::Captures, policy, op)
+    }
+    /// Inspects all legal quiet moves (except for promotions) in position with a function.
+    /// Returns `false` if there are no such moves.
+    ///
+    /// Unlike [`Self::for_each_legal_child_node`] this cannot detect a [`GameEnding`],
+    /// since captures are never generated.
+    #[inline(always)]
+    pub fn for_each_legal_quiet_node<F>(&mut self, policy: MoveOrdering, op: F) -> bool
+    where
+        F: FnMut(&mut Self, ChessMove),
+    {
+        self.for_each_legal_node_of(MoveKind::Quiets, policy, op)
+    }
     /// Skips all next moves if currently
     /// inspecting legal moves using [`Self::for_each_legal_child_node`].
     ///
+    /// Stages of move generation that were not reached yet will be skipped entirely.
+    ///
     /// # Panics
     /// Panics if not currently
     /// inspecting legal moves using [`Self::for_each_legal_child_node`].
     pub fn exhaust_moves(&mut self) {
-        loop {
-            if self.move_list.pop_move().is_none() {
-                break;
+        self.move_list.exhaust_group();
+    }
+    /// Inspects the legal moves of the specified kind with a function.
+    /// Returns `true` if at least one of the moves was legal.
+    #[inline(always)]
+    fn for_each_legal_node_of<F>(&mut self, kind: MoveKind, policy: MoveOrdering, mut op: F) -> bool
+    where
+        F: FnMut(&mut Self, ChessMove),
+    {
+        self.move_list.push_group();
+
+        let hash_move = policy
+            .hash_move()
+            .filter(|&m| self.game.is_move_pseudo_legal(m))
+            .filter(|m| match m.hint().is_capture() || m.hint().is_promotion() {
+                true => kind.has_captures(),
+                false => kind.has_quiets(),
+            });
+        let killers = policy.killers().map(|killer| {
+            killer
+                .filter(|&m| Some(m) != hash_move)
+                .filter(|m| !m.hint().is_capture() && !m.hint().is_promotion())
+                .filter(|&m| kind.has_quiets() && self.game.is_move_pseudo_legal(m))
+        });
+
+        let mut has_moves = false;
+        'stages: {
+            if let Some(hash_move) = hash_move {
+                self.move_list.push_move(hash_move);
+                if !self.visit_moves(&mut has_moves, &mut op) {
+                    break 'stages;
+                }
+            }
+            if kind.has_captures() {
+                self.generate_captures(policy, hash_move);
+                if !self.visit_moves(&mut has_moves, &mut op) {
+                    break 'stages;
+                }
+            }
+            if kind.has_quiets() {
+                // Killer moves are pushed in reverse, since they are popped from the end.
+                for killer in killers.into_iter().rev().flatten() {
+                    self.move_list.push_move(killer);
+                }
+                if !self.visit_moves(&mut has_moves, &mut op) {
+                    break 'stages;
+                }
+                self.generate_quiets(hash_move, killers);
+                self.visit_moves(&mut has_moves, &mut op);
             }
         }
+
+        self.move_list.pop_group();
+        has_moves
     }
-    fn generate_moves(&mut self, policy: MoveOrdering) {
-        self.game.push_moves(&mut |chess_move| {
-            self.move_list.push_move(chess_move);
+    /// Inspects the moves from the current move group with a function.
+    /// Returns `false` if the moves were exhausted.
+    #[inline(always)]
+    fn visit_moves<F>(&mut self, has_moves: &mut bool, op: &mut F) -> bool
+    where
+        F: FnMut(&mut Self, ChessMove),
+    {
+        while let Some(chess_move) = self.move_list.pop_move() {
+            if self.game.make_move_unchecked(chess_move) {
+                *has_moves = true;
+                op(self, chess_move);
+                self.game.unmake_move();
+            }
+        }
+        !self.move_list.is_group_exhausted()
+    }
+    fn generate_captures(&mut self, policy: MoveOrdering, hash_move: Option<ChessMove>) {
+        self.game.push_captures(&mut |chess_move| {
+            if Some(chess_move) != hash_move {
+                self.move_list.push_move(chess_move);
+            }
         });
         if policy == MoveOrdering::CapturesFirst {
             return;
         }
 
+        let board = self.game.board();
         let moves = self.move_list.group_mut();
         moves.sort_by_cached_key(|k| {
-            let piece = self.game.board().get_piece_at(k.get().dest_square());
-            match piece {
-                Some(piece) => piece as i32,
-                None => -1,
+            let chess_move = k.get();
+            let victim = match chess_move.hint() {
+                ChessMoveHint::EnPassantCapture => Some(Piece::Pawn),
+                _ => board.get_piece_at(chess_move.dest_square()),
+            };
+            let promotion = chess_move.hint().promotion();
+            let attacker = board.get_piece_at(chess_move.orig_square()).unwrap();
+            // Moves are popped from the end, so the most valuable
+            // victims and the least valuable attackers go last.
+            let victim_value = victim.map_or(0, |p| p as i32 + 1);
+            let promotion_value = promotion.map_or(0, |p| p.piece() as i32);
+            (victim_value + promotion_value) * 8 - attacker as i32
+        });
+    }
+    fn generate_quiets(&mut self, hash_move: Option<ChessMove>, killers: [Option<ChessMove>; 2]) {
+        self.game.push_quiets(&mut |chess_move| {
+            if Some(chess_move) != hash_move && !killers.contains(&Some(chess_move)) {
+                self.move_list.push_move(chess_move);
             }
         });
     }
@@ -4,7 +4,14 @@ use crate::{game::PackedChessMove, ChessMove};
 #[derive(Debug, Clone)]
 pub struct MoveList {
     moves: Vec<PackedChessMove>,
-    lens: Vec<usize>,
+    groups: Vec<Group>,
+}
+
+/// Bookkeeping for a single move group.
+#[derive(Debug, Clone, Copy)]
+struct Group {
+    len: usize,
+    is_exhausted: bool,
 }
 
 impl MoveList {
@@ -14,7 +21,7 @@ impl MoveList {
     pub fn empty() -> MoveList {
         MoveList {
             moves: Vec::new(),
-            lens: Vec::new(),
+            groups: Vec::new(),
         }
     }
     /// Returns a mutable slice to the current move group.
@@ -48,28 +55,52 @@ impl MoveList {
         self.moves.push(PackedChessMove::new(chess_move));
         *self.group_len_mut() += 1;
     }
+    /// Removes all moves from the current move group and marks it as exhausted.
+    ///
+    /// # Panics
+    /// Panics if there is no current move group.
+    #[inline(always)]
+    pub fn exhaust_group(&mut self) {
+        let len = self.group_len();
+        self.moves.truncate(self.moves.len() - len);
+        let group = self.groups.last_mut().unwrap();
+        group.len = 0;
+        group.is_exhausted = true;
+    }
+    /// Returns `true` if [`Self::exhaust_group`] was called on the current move group.
+    ///
+    /// # Panics
+    /// Panics if there is no current move group.
+    #[inline(always)]
+    #[must_use]
+    pub fn is_group_exhausted(&self) -> bool {
+        self.groups.last().unwrap().is_exhausted
+    }
     /// Discard current move group and restores the old move group (if any).
     ///
     /// # Panics
     /// Panics if there is no current move group.
     #[inline(always)]
     pub fn pop_group(&mut self) {
-        let len = self.lens.pop().expect("move list has no more groups");
-        self.moves.truncate(self.moves.len() - len);
+        let group = self.groups.pop().expect("move list has no more groups");
+        self.moves.truncate(self.moves.len() - group.len);
     }
     /// Saves the old move group (if any) and starts a new one (current).
     #[inline(always)]
     pub fn push_group(&mut self) {
-        self.lens.push(0);
+        self.groups.push(Group {
+            len: 0,
+            is_exhausted: false,
+        });
     }
     #[inline(always)]
     #[must_use]
     fn group_len(&self) -> usize {
-        *self.lens.last().unwrap()
+        self.groups.last().unwrap().len
     }
     #[inline(always)]
     #[must_use]
     fn group_len_mut(&mut self) -> &mut usize {
-        self.lens.last_mut().unwrap()
+        &mut self.groups.last_mut().unwrap().len
     }
 }
@@ -1,6 +1,5 @@
 use crate::{CastlingRights, Color, File, Piece, Square};
 
-use const_random::const_random;
 use strum::EnumCount;
 
 /// Computes a [zobrist hash] for a chess piece.
@@ -10,8 +9,10 @@ use strum::EnumCount;
 #[inline(always)]
 pub fn get_square_zobrist(color: Color, piece: Piece, sq: Square) -> u64 {
     const TABLE_SIZE: usize = Piece::COUNT * Color::COUNT * Square::COUNT;
-    const PIECE_AT_SQUARE_RANDOMS: [u64; TABLE_SIZE] = make_random_u64_table::<TABLE_SIZE>();
-    PIECE_AT_SQUARE_RANDOMS[(piece as usize) * (color as usize) * (sq as usize)]
+    const PIECE_AT_SQUARE_RANDOMS: [u64; TABLE_SIZE] =
+        make_random_u64_table::<TABLE_SIZE>(0x5EED_0001);
+    let index = ((color as usize) * Piece::COUNT + piece as usize) * Square::COUNT + sq as usize;
+    PIECE_AT_SQUARE_RANDOMS[index]
 }
 
 /// Computes a [zobrist hash] for the position's turn.
@@ -21,7 +22,7 @@ pub fn get_square_zobrist(color: Color, piece: Piece, sq: Square) -> u64 {
 #[inline(always)]
 pub fn get_turn_zobrist(turn: Color) -> u64 {
     const TABLE_SIZE: usize = Color::COUNT;
-    const COLOR_RANDOMS: [u64; TABLE_SIZE] = make_random_u64_table::<TABLE_SIZE>();
+    const COLOR_RANDOMS: [u64; TABLE_SIZE] = make_random_u64_table::<TABLE_SIZE>(0x5EED_0002);
     COLOR_RANDOMS[turn as usize]
 }
 
@@ -32,7 +33,7 @@ pub fn get_turn_zobrist(turn: Color) -> u64 {
 #[inline(always)]
 pub fn get_en_passant_zobrist(en_passant: Option<File>) -> u64 {
     const TABLE_SIZE: usize = File::COUNT + 1;
-    const EN_PASSANT_RANDOMS: [u64; TABLE_SIZE] = make_random_u64_table::<TABLE_SIZE>();
+    const EN_PASSANT_RANDOMS: [u64; TABLE_SIZE] = make_random_u64_table::<TABLE_SIZE>(0x5EED_0003);
     match en_passant {
         Some(file) => EN_PASSANT_RANDOMS[file as usize + 1],
         None => EN_PASSANT_RANDOMS[0],
@@ -46,17 +47,29 @@ pub fn get_en_passant_zobrist(en_passant: Option<File>) -> u64 {
 #[inline(always)]
 pub fn get_castling_zobrist(castling_rights: CastlingRights) -> u64 {
     const TABLE_SIZE: usize = CastlingRights::all().bits() as usize + 1;
-    const CASTLING_RANDOMS: [u64; TABLE_SIZE] = make_random_u64_table::<TABLE_SIZE>();
+    const CASTLING_RANDOMS: [u64; TABLE_SIZE] = make_random_u64_table::<TABLE_SIZE>(0x5EED_0004);
     CASTLING_RANDOMS[castling_rights.bits() as usize]
 }
 
-/// Computes a random table of `u64`s. It is unique each call.
+/// Computes a table of pseudo-random `u64`s from a seed.
+///
+/// The seeds are fixed, so that the hashes are the same for every build
+/// and can be compared between machines. Each table must use its own seed.
+///
+/// The table is expanded with [SplitMix64].
+///
+/// [SplitMix64]: https://prng.di.unimi.it/splitmix64.c
 #[must_use]
-const fn make_random_u64_table<const SIZE: usize>() -> [u64; SIZE] {
+const fn make_random_u64_table<const SIZE: usize>(seed: u64) -> [u64; SIZE] {
     let mut result = [0; SIZE];
+    let mut state = seed;
     let mut i = 0;
     while i < SIZE {
-        result[i] = const_random!(u64);
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        result[i] = z ^ (z >> 31);
         i += 1;
     }
     result
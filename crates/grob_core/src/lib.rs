@@ -6,7 +6,8 @@ pub use primitives::{
     BitBoard, Board, CastlingRights, Color, File, NegDiag, Piece, PosDiag, Promotion, Rank, Square,
 };
 pub use search::{
-    spawn_search_server, Score, SearchRequest, SearchResult, ServerCommand, ServerResponse,
+    spawn_search_server, HashStats, Pv, Score, SearchRequest, SearchResponse, SearchResult,
+    ServerCommand, ServerResponse, Window,
 };
 
 pub use game::{
@@ -14,6 +15,9 @@ pub use game::{
     PackedChessMove, ParseFenError,
 };
 
+pub mod book;
+pub mod eval;
+
 mod game;
 mod primitives;
 mod search;
@@ -12,7 +12,7 @@ use strum::EnumCount;
 /// A [bitboard]. Wraps u64 occupancy mask.
 ///
 /// [bitboard]: https://www.chessprogramming.org/Bitboard_Board-Definition
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub struct BitBoard(pub u64);
 
 impl BitBoard {
@@ -1,8 +1,11 @@
+pub use pv::Pv;
 pub use scheduler::{
-    spawn_search_server, SearchRequest, SearchResult, ServerCommand, ServerResponse,
+    spawn_search_server, SearchRequest, SearchResponse, SearchResult, ServerCommand, ServerResponse,
 };
-pub use score::Score;
+pub use score::{Score, Window};
+pub use transposition::HashStats;
 
+mod pv;
 mod scheduler;
 mod score;
 mod signals;
@@ -0,0 +1,61 @@
+use std::fmt;
+
+use crate::ChessMove;
+
+/// A [principal variation] - the line of play that the search
+/// considers best for both players.
+///
+/// Moves are stored inline, so that the [`SearchResult`](crate::SearchResult)
+/// stays [`Copy`]. Lines longer than [`Pv::MAX_LEN`] are truncated.
+///
+/// [principal variation]: https://www.chessprogramming.org/Principal_Variation
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Pv {
+    moves: [Option<ChessMove>; Pv::MAX_LEN],
+}
+
+impl Pv {
+    /// Maximum number of moves in a [`Pv`].
+    pub const MAX_LEN: usize = 32;
+    /// A line without any moves.
+    pub const EMPTY: Pv = Pv {
+        moves: [None; Pv::MAX_LEN],
+    };
+
+    /// Constructs a line that starts with a move followed by
+    /// the line of the resulting position.
+    pub fn new(chess_move: ChessMove, rest: &Pv) -> Self {
+        let mut moves = [None; Self::MAX_LEN];
+        moves[0] = Some(chess_move);
+        moves[1..].copy_from_slice(&rest.moves[..Self::MAX_LEN - 1]);
+        Self { moves }
+    }
+    /// Returns the number of moves in the line.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+    /// Returns `true` if the line has no moves.
+    pub fn is_empty(&self) -> bool {
+        self.moves[0].is_none()
+    }
+    /// Returns the first move of the line.
+    pub fn first(&self) -> Option<ChessMove> {
+        self.moves[0]
+    }
+    /// Returns an iterator over the moves of the line.
+    pub fn iter(&self) -> impl Iterator<Item = ChessMove> + '_ {
+        self.moves.iter().map_while(|&chess_move| chess_move)
+    }
+}
+
+impl Default for Pv {
+    fn default() -> Self {
+        Self::EMPTY
+    }
+}
+
+impl fmt::Debug for Pv {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
@@ -6,11 +6,12 @@ use crossbeam::{
 };
 
 use crate::{
+    eval::{EvaluatorFactory, TaperedEvaluator},
     search::{
-        transposition::TranspositionTable,
+        transposition::{HashStats, TranspositionTable},
         worker::{Job, WorkerGroup},
     },
-    ChessMove, Game, Score,
+    ChessMove, Color, Game, Pv, Score, Window,
 };
 
 /// A command for the parallel search server.
@@ -38,15 +39,25 @@ pub enum ServerCommand {
     Cancel,
     /// Immediately clears all data from the transposition table.
     ///
+    /// This also starts a new search (see [`ServerCommand::NewSearch`]).
+    ///
     /// # Performance
     /// This is a slow operation and it may cause the ongoing search
     /// to miss its deadline.
     ClearHash,
-    /// Immediately resize the transposition table to be as large
+    /// Tells the server that the following batches belong to a new search.
+    ///
+    /// Transpositions saved during the previous searches will be
+    /// replaced more eagerly than the ones from the new search.
+    /// Move ordering statistics of the previous searches are forgotten.
+    NewSearch,
+    /// Replace the transposition table with an empty one, that is as large
     /// as possible but no more than a specified number of mebibytes (MiB).
     ///
     /// Transposition table size limit cannot be smaller than 1 MiB.
     ///
+    /// Ongoing search will keep using the old table until it finishes.
+    ///
     /// # Performance
     /// This is a slow operation and it may cause the ongoing search
     /// to miss its deadline.
@@ -54,6 +65,10 @@ pub enum ServerCommand {
         /// The size limit in mebibytes (MiB).
         max_mib: usize,
     },
+    /// Request the [`HashStats`] of the transposition table.
+    ///
+    /// Server will reply with [`ServerResponse::HashStats`].
+    GetHashStats,
     /// Change the amount of worker threads to be used in the future
     /// searches.
     ///
@@ -62,6 +77,29 @@ pub enum ServerCommand {
     ///
     /// Search will always use at least one worker thread.
     SetWorkerCount(usize),
+    /// Change how the draws are scored in the future searches.
+    ///
+    /// Draws are worth `-contempt` centi-pawns for the `side`
+    /// and `contempt` centi-pawns for its opponent, so the positive
+    /// contempt makes the `side` avoid the draws.
+    ///
+    /// Contempt is zero by default.
+    SetContempt {
+        /// The contempt in centi-pawns.
+        contempt: i32,
+        /// The player the contempt is applied to.
+        side: Color,
+    },
+    /// Change the static evaluation used in the future searches.
+    ///
+    /// Every worker thread gets its own [`Evaluator`] created by the factory.
+    /// [`TaperedEvaluator`] is used by default.
+    ///
+    /// This will **NOT** influence the evaluation used in the
+    /// ongoing search.
+    ///
+    /// [`Evaluator`]: crate::eval::Evaluator
+    SetEvaluator(EvaluatorFactory),
 }
 
 /// Request to search a position.
@@ -75,12 +113,27 @@ pub struct SearchRequest {
     pub nodes: Option<u64>,
     /// Search time limit.
     pub deadline: Option<Instant>,
+    /// Scores the search is interested in, use [`Window::FULL`]
+    /// to always get the exact score.
+    ///
+    /// The [`SearchResult::score`] outside of the window is only a bound.
+    pub window: Window,
+}
+
+/// A response from the parallel search server.
+#[derive(Debug, Clone, Copy)]
+pub enum ServerResponse {
+    /// Processing results for a [`SearchRequest`] originating from
+    /// [`ServerCommand::ProcessBatch`].
+    Search(SearchResponse),
+    /// Statistics requested with [`ServerCommand::GetHashStats`].
+    HashStats(HashStats),
 }
 
 /// Processing results for a [`SearchRequest`] originating from
 /// [`ServerCommand::ProcessBatch`].
 #[derive(Debug, Clone, Copy)]
-pub struct ServerResponse {
+pub struct SearchResponse {
     /// Index of the corresponding [`SearchRequest`] inside of
     /// [`ServerCommand::ProcessBatch`].
     pub batch_index: usize,
@@ -91,12 +144,17 @@ pub struct ServerResponse {
 /// Result of searching a position.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct SearchResult {
-    /// Proposed score of the position.
+    /// Proposed score of the position for the player who is about to make a turn.
     pub score: Score,
     /// Number of nodes searched.
     pub nodes: u64,
-    /// Proposed best move (or `None` if no legal moves are available).
+    /// Length of the longest line searched in plies, including
+    /// the [quiescence search](https://www.chessprogramming.org/Quiescence_Search).
+    pub seldepth: u64,
+    /// Proposed best move (or `None` if no moves were searched).
     pub best_move: Option<ChessMove>,
+    /// Expected line of play starting with the [`Self::best_move`].
+    pub pv: Pv,
     /// Whether the corresponding[`SearchRequest`] was abruptly
     /// canceled with [`ServerCommand::Cancel`].
     pub is_canceled: bool,
@@ -126,10 +184,16 @@ struct SearchScheduler {
     rsp_send: Sender<ServerResponse>,
     cmd_recv: Receiver<ServerCommand>,
     job_send: Sender<Job>,
-    res_recv: Receiver<ServerResponse>,
+    res_recv: Receiver<SearchResponse>,
     pending_count: usize,
     worker_count: usize,
     tt: Arc<TranspositionTable>,
+    /// Contempt of the white player.
+    contempt: i32,
+    /// Identifier of the current search (see [`Job::search_id`]).
+    search_id: u64,
+    /// Evaluator to be used once the ongoing search finishes.
+    pending_evaluator: Option<EvaluatorFactory>,
 }
 
 /// Simple utility type to improve control flow.
@@ -153,7 +217,12 @@ impl SearchScheduler {
         let (res_send, res_recv) = unbounded();
         let tt_capacity = tt_max_capacity_mib.max(1) * 1024 * 1024 / TranspositionTable::ITEM_SIZE;
         let tt = Arc::new(TranspositionTable::new(tt_capacity));
-        let workers = WorkerGroup::new(worker_count.max(1), job_recv, res_send, tt.clone());
+        let workers = WorkerGroup::new(
+            worker_count.max(1),
+            job_recv,
+            res_send,
+            TaperedEvaluator::boxed,
+        );
 
         Self {
             workers,
@@ -164,6 +233,9 @@ impl SearchScheduler {
             worker_count,
             pending_count: 0,
             tt,
+            contempt: 0,
+            search_id: 0,
+            pending_evaluator: None,
         }
     }
     /// Run the scheduler's command execution loop.
@@ -196,15 +268,21 @@ impl SearchScheduler {
         match cmd {
             ServerCommand::ProcessBatch(batch) => self.process_batch(batch)?,
             ServerCommand::Cancel => self.cancel()?,
-            ServerCommand::ClearHash => self.tt.clear(),
+            ServerCommand::ClearHash => self.clear_hash(),
+            ServerCommand::NewSearch => self.new_search(),
             ServerCommand::SetHashSize { max_mib } => self.set_hash_size(max_mib),
+            ServerCommand::GetHashStats => self.send_hash_stats()?,
             ServerCommand::SetWorkerCount(worker_count) => self.set_worker_count(worker_count),
+            ServerCommand::SetContempt { contempt, side } => self.set_contempt(contempt, side),
+            ServerCommand::SetEvaluator(evaluator) => self.set_evaluator(evaluator),
         }
         Ok(())
     }
     /// Forward the search result to the user.
-    fn forward_response(&mut self, rsp: ServerResponse) -> Result {
-        self.rsp_send.send(rsp).map_err(|SendError(_)| ShouldQuit)?;
+    fn forward_response(&mut self, rsp: SearchResponse) -> Result {
+        self.rsp_send
+            .send(ServerResponse::Search(rsp))
+            .map_err(|SendError(_)| ShouldQuit)?;
         self.pending_count -= 1;
         Ok(())
     }
@@ -213,12 +291,16 @@ impl SearchScheduler {
         if self.workers.signaler().is_running() {
             self.cancel()?;
         }
+        self.update_workers();
 
         self.pending_count = batch.len();
         for (batch_index, request) in batch.into_iter().enumerate() {
             let job = Job {
                 request,
                 batch_index,
+                tt: self.tt.clone(),
+                contempt: self.contempt,
+                search_id: self.search_id,
             };
             self.job_send.send(job).unwrap();
         }
@@ -232,16 +314,51 @@ impl SearchScheduler {
             let rsp = self.res_recv.recv().map_err(|RecvError| ShouldQuit)?;
             self.forward_response(rsp)?;
         }
-        self.workers.resize(self.worker_count);
+        self.update_workers();
         Ok(())
     }
+    /// Apply the worker settings that were changed since the last search.
+    fn update_workers(&mut self) {
+        self.workers.resize(self.worker_count);
+        if let Some(evaluator) = self.pending_evaluator.take() {
+            self.workers.set_evaluator(evaluator);
+        }
+    }
+    /// Execute [`ServerCommand::ClearHash`].
+    fn clear_hash(&mut self) {
+        self.tt.clear();
+        self.search_id += 1;
+    }
+    /// Execute [`ServerCommand::NewSearch`].
+    fn new_search(&mut self) {
+        self.tt.new_search();
+        self.search_id += 1;
+    }
     /// Execute [`ServerCommand::SetHashSize`].
     fn set_hash_size(&mut self, max_mib: usize) {
         let new_capacity = max_mib.max(1) * 1024 * 1024 / TranspositionTable::ITEM_SIZE;
-        self.tt.resize(new_capacity);
+        self.tt = Arc::new(TranspositionTable::new(new_capacity));
+    }
+    /// Execute [`ServerCommand::GetHashStats`].
+    fn send_hash_stats(&mut self) -> Result {
+        let stats = self.tt.stats();
+        self.rsp_send
+            .send(ServerResponse::HashStats(stats))
+            .map_err(|SendError(_)| ShouldQuit)
     }
     /// Execute [`ServerCommand::SetWorkerCount`]
     fn set_worker_count(&mut self, worker_count: usize) {
         self.worker_count = worker_count.max(1)
     }
+    /// Execute [`ServerCommand::SetContempt`]
+    fn set_contempt(&mut self, contempt: i32, side: Color) {
+        self.contempt = match side {
+            Color::White => contempt,
+            Color::Black => contempt.saturating_neg(),
+        };
+    }
+    /// Execute [`ServerCommand::SetEvaluator`]
+    fn set_evaluator(&mut self, evaluator: EvaluatorFactory) {
+        self.pending_evaluator = Some(evaluator);
+    }
 }
@@ -13,10 +13,16 @@ pub enum Score {
 
 impl Score {
     /// Returns the socre for the [`GameEnding`].
-    pub fn ending(ending: GameEnding) -> Self {
+    ///
+    /// Draws are scored as `-contempt`, where `contempt` is how much
+    /// the player who is about to make a turn wants to avoid a draw.
+    pub fn ending(ending: GameEnding, contempt: i32) -> Self {
         match ending {
-            GameEnding::Stalemate => Self::Cp(0),
             GameEnding::Checkmate => Self::Mated(0),
+            GameEnding::Stalemate
+            | GameEnding::Repetition
+            | GameEnding::FiftyMoves
+            | GameEnding::InsufficientMaterial => Self::Cp(contempt.saturating_neg()),
         }
     }
     /// Returns the score for the other player on the previous turn.
@@ -27,14 +33,79 @@ impl Score {
             Score::Cp(i) => Score::Cp(-i),
         }
     }
+    /// Returns the score with the centi-pawns added to it.
+    ///
+    /// Mate scores are returned unchanged.
+    pub fn add_cp(self, cp: i32) -> Self {
+        match self {
+            Score::Cp(i) => Score::Cp(i.saturating_add(cp)),
+            mate => mate,
+        }
+    }
     /// Returns the score for the other player on the next turn.
     pub fn next(self) -> Self {
         match self {
-            Score::Mating(n) => Score::Mated(n - 1),
+            Score::Mating(n) => Score::Mated(n.saturating_sub(1)),
             Score::Mated(n) => Score::Mating(n),
             Score::Cp(i) => Score::Cp(-i),
         }
     }
+    /// Returns the smallest score that is better than this one.
+    ///
+    /// The best score ([`Score::Mating(0)`](Score::Mating)) is returned unchanged.
+    pub fn successor(self) -> Self {
+        match self {
+            Score::Mating(n) => Score::Mating(n.saturating_sub(1)),
+            Score::Mated(u64::MAX) => Score::Cp(i32::MIN),
+            Score::Mated(n) => Score::Mated(n + 1),
+            Score::Cp(i32::MAX) => Score::Mating(u64::MAX),
+            Score::Cp(i) => Score::Cp(i + 1),
+        }
+    }
+}
+
+/// Range of the scores a search is interested in.
+///
+/// Scores that fall outside of the window are only bounds: a score
+/// `<= alpha` is an upper bound and a score `>= beta` is a lower bound
+/// of the real one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Window {
+    /// Score the player is already guaranteed to get.
+    pub alpha: Score,
+    /// Score the opponent is already guaranteed to hold the player to.
+    pub beta: Score,
+}
+
+impl Window {
+    /// Window that does not exclude any score.
+    pub const FULL: Self = Self {
+        alpha: Score::Mated(0),
+        beta: Score::Mating(1),
+    };
+
+    /// Returns a window that only tells whether the score is above `alpha`.
+    pub fn null(alpha: Score) -> Self {
+        Self {
+            alpha,
+            beta: alpha.successor(),
+        }
+    }
+    /// Returns the window for the other player on the next turn.
+    pub fn next(self) -> Self {
+        Self {
+            alpha: self.beta.next(),
+            beta: self.alpha.next(),
+        }
+    }
+    /// Returns whether the score searched with this window is exact.
+    ///
+    /// The bounds of [`Window::FULL`] can not be exceeded,
+    /// so they do not make the score inexact.
+    pub fn is_exact(self, score: Score) -> bool {
+        (score > self.alpha || self.alpha == Self::FULL.alpha)
+            && (score < self.beta || self.beta == Self::FULL.beta)
+    }
 }
 
 impl PartialOrd for Score {
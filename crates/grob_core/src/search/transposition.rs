@@ -1,4 +1,4 @@
-pub use table::{Transposition, TranspositionTable};
+pub use table::{Bound, HashStats, TableUsage, Transposition, TranspositionTable};
 
+mod bucket;
 mod table;
-mod table_base;
@@ -0,0 +1,207 @@
+use std::{
+    num::NonZeroU64,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use crate::{
+    search::transposition::table::{Bound, Transposition},
+    PackedChessMove, Score,
+};
+
+/// Number of entries in a single [`Bucket`].
+pub const BUCKET_LEN: usize = 4;
+
+/// Number of distinct generations that can be told apart.
+pub const GENERATION_COUNT: u8 = 1 << 6;
+
+/// A group of entries that share the same index in the table.
+///
+/// Bucket is exactly as large as a cache line so that a probe
+/// never has to touch more than one.
+#[repr(align(64))]
+#[derive(Debug, Default)]
+pub struct Bucket(pub [Entry; BUCKET_LEN]);
+
+/// A lock-free slot for a single [`Transposition`].
+///
+/// The key is stored XOR-ed with the data, so that an entry
+/// torn by concurrent writes fails to verify instead of
+/// returning somebody else's data.
+#[derive(Debug, Default)]
+pub struct Entry {
+    check: AtomicU64,
+    data: AtomicU64,
+}
+
+/// Contents of an [`Entry`] at the time it was loaded.
+#[derive(Debug, Clone, Copy)]
+pub struct Slot {
+    key: u64,
+    data: u64,
+}
+
+impl Entry {
+    /// Loads the current contents of the entry.
+    #[inline(always)]
+    pub fn load(&self) -> Slot {
+        let data = self.data.load(Ordering::Relaxed);
+        let check = self.check.load(Ordering::Relaxed);
+        Slot {
+            key: check ^ data,
+            data,
+        }
+    }
+    /// Overwrites the entry.
+    #[inline(always)]
+    pub fn store(&self, key: NonZeroU64, value: Transposition, generation: u8) {
+        let data = pack(value, generation);
+        self.data.store(data, Ordering::Relaxed);
+        self.check.store(key.get() ^ data, Ordering::Relaxed);
+    }
+    /// Empties the entry.
+    pub fn clear(&self) {
+        self.data.store(0, Ordering::Relaxed);
+        self.check.store(0, Ordering::Relaxed);
+    }
+}
+
+impl Slot {
+    /// Returns `true` if nothing was ever stored in the entry.
+    #[inline(always)]
+    pub fn is_empty(self) -> bool {
+        self.data == 0
+    }
+    /// Returns `true` if the entry holds a transposition for this key.
+    #[inline(always)]
+    pub fn has_key(self, key: NonZeroU64) -> bool {
+        !self.is_empty() && self.key == key.get()
+    }
+    /// Returns the stored transposition or `None` if the entry is empty.
+    #[inline(always)]
+    pub fn transposition(self) -> Option<Transposition> {
+        unpack(self.data)
+    }
+    /// Returns the generation during which the entry was stored.
+    #[inline(always)]
+    pub fn generation(self) -> u8 {
+        (self.data >> 58) as u8
+    }
+    /// Returns the depth of the stored transposition.
+    #[inline(always)]
+    pub fn depth(self) -> u8 {
+        (self.data >> 48) as u8
+    }
+}
+
+/// Score used to encode mates. It is just outside of the range of `Cp` scores.
+const MATE_SCORE: i32 = i32::MAX;
+/// Largest possible encoded `Cp` score by its absolute value.
+const CP_MAX: i32 = 1 << 30;
+
+/// Packs the [`Transposition`] into 64 bits.
+///
+/// | bits    | contents              |
+/// |---------|-----------------------|
+/// | 0..16   | best move             |
+/// | 16..48  | score                 |
+/// | 48..56  | depth (saturated)     |
+/// | 56..58  | bound (never zero)    |
+/// | 58..64  | generation            |
+fn pack(value: Transposition, generation: u8) -> u64 {
+    let chess_move = PackedChessMove::new(value.best_move).to_bits() as u64;
+    let score = match value.score {
+        Score::Cp(cp) => cp.clamp(-CP_MAX, CP_MAX),
+        Score::Mating(n) => MATE_SCORE - n.min(CP_MAX as u64) as i32,
+        Score::Mated(n) => -MATE_SCORE + n.min(CP_MAX as u64) as i32,
+    } as u32 as u64;
+    let depth = value.depth.min(u8::MAX as u64);
+    let bound: u64 = match value.bound {
+        Bound::Exact => 1,
+        Bound::Lower => 2,
+        Bound::Upper => 3,
+    };
+    let generation = (generation % GENERATION_COUNT) as u64;
+    chess_move | (score << 16) | (depth << 48) | (bound << 56) | (generation << 58)
+}
+
+/// Unpacks the [`Transposition`] packed with [`pack`].
+fn unpack(data: u64) -> Option<Transposition> {
+    let best_move = PackedChessMove::from_bits(data as u16)?.get();
+    let score = match (data >> 16) as u32 as i32 {
+        s if s > CP_MAX => Score::Mating((MATE_SCORE - s) as u64),
+        s if s < -CP_MAX => Score::Mated((s + MATE_SCORE) as u64),
+        s => Score::Cp(s),
+    };
+    let depth = (data >> 48) as u8 as u64;
+    let bound = match (data >> 56) & 0b11 {
+        1 => Bound::Exact,
+        2 => Bound::Lower,
+        3 => Bound::Upper,
+        _ => return None,
+    };
+    Some(Transposition {
+        best_move,
+        score,
+        depth,
+        bound,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Game;
+
+    #[test]
+    fn test_pack_unpack() {
+        let mut game = Game::initial_position();
+        let best_move = game.walk().check_ending().left().unwrap();
+        let scores = [
+            Score::Cp(0),
+            Score::Cp(-123),
+            Score::Cp(i32::MAX),
+            Score::Mating(1),
+            Score::Mating(42),
+            Score::Mated(0),
+            Score::Mated(17),
+        ];
+        for (i, score) in scores.into_iter().enumerate() {
+            for bound in [Bound::Exact, Bound::Lower, Bound::Upper] {
+                let value = Transposition {
+                    best_move,
+                    score,
+                    depth: i as u64,
+                    bound,
+                };
+                let unpacked = unpack(pack(value, 5)).unwrap();
+                let expected_score = match score {
+                    Score::Cp(cp) => Score::Cp(cp.min(CP_MAX)),
+                    mate => mate,
+                };
+                assert_eq!(unpacked.best_move, best_move);
+                assert_eq!(unpacked.score, expected_score);
+                assert_eq!(unpacked.depth, i as u64);
+                assert_eq!(unpacked.bound, bound);
+            }
+        }
+    }
+
+    #[test]
+    fn test_torn_entry_is_rejected() {
+        let mut game = Game::initial_position();
+        let best_move = game.walk().check_ending().left().unwrap();
+        let value = Transposition {
+            best_move,
+            score: Score::Cp(10),
+            depth: 3,
+            bound: Bound::Exact,
+        };
+        let key = NonZeroU64::new(0xdead_beef).unwrap();
+        let entry = Entry::default();
+        assert!(!entry.load().has_key(key));
+        entry.store(key, value, 0);
+        assert!(entry.load().has_key(key));
+        entry.data.store(pack(value, 1), Ordering::Relaxed);
+        assert!(!entry.load().has_key(key));
+    }
+}
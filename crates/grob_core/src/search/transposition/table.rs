@@ -1,8 +1,13 @@
-use std::{fmt, num::NonZeroU64};
+use std::{
+    fmt,
+    num::NonZeroU64,
+    sync::atomic::{AtomicU64, AtomicU8, Ordering},
+};
 
-use parking_lot::RwLock;
-
-use crate::{search::transposition::table_base::TranspositionTableBase, ChessMove, Score};
+use crate::{
+    search::transposition::bucket::{Bucket, Entry, BUCKET_LEN, GENERATION_COUNT},
+    ChessMove, Score,
+};
 
 /// A [transposition].
 ///
@@ -15,83 +20,399 @@ pub struct Transposition {
     /// Best move on the basis of the past search.
     pub best_move: ChessMove,
     /// The [`Score`] of the position on the basis of the past search.
+    ///
+    /// Just like any other [`Score`] produced by the search, mate distances
+    /// are counted from the stored position and not from the root.
     pub score: Score,
     /// The depth of the past search.
     pub depth: u64, // TODO: is u64 too large?
+    /// How the [`Self::score`] relates to the real score of the position.
+    pub bound: Bound,
+}
+
+/// Kind of the bound that the [`Transposition::score`] represents.
+///
+/// Alpha-beta search only finds the exact score for the positions where
+/// the score falls inside of the search window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    /// The score is exact.
+    Exact,
+    /// The real score is at least as good (the search failed high).
+    Lower,
+    /// The real score is at most as good (the search failed low).
+    Upper,
+}
+
+/// Usage statistics of the transposition table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct HashStats {
+    /// Maximum number of transpositions the table can hold.
+    pub capacity: usize,
+    /// Approximate share of the table occupied by the transpositions
+    /// from the current search in per-mille (1/1000).
+    ///
+    /// This is computed from a sample of the first 1000 entries.
+    pub hashfull: u16,
+    /// Number of probes that found a transposition for the position.
+    pub hits: u64,
+    /// Number of probes that did not find a transposition for the position.
+    pub misses: u64,
+    /// Number of transpositions that were replaced by the transpositions
+    /// of different positions.
+    pub overwrites: u64,
+}
+
+/// Usage counters of the transposition table accumulated by a single thread.
+///
+/// Counting locally and merging them with [`TranspositionTable::record_usage`]
+/// keeps the threads from fighting over the shared counters on every probe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TableUsage {
+    /// See [`HashStats::hits`].
+    pub hits: u64,
+    /// See [`HashStats::misses`].
+    pub misses: u64,
+    /// See [`HashStats::overwrites`].
+    pub overwrites: u64,
 }
 
 /// A [transposition table].
 ///
-/// Transposition table uses an `RwLock` internally so that
-/// it can safely be shared between threads.
+/// Transposition table is lock-free, so that it can be shared between
+/// threads without them waiting on each other. Transpositions are grouped
+/// into cache-line-sized buckets and a [`Transposition`] that is torn by
+/// concurrent writes is discarded on read.
+///
+/// When a bucket is full, the transpositions from the older searches
+/// (see [`Self::new_search`]) and the shallower ones are replaced first.
 ///
 /// [transposition table]: https://www.chessprogramming.org/Transposition_Table
-pub struct TranspositionTable(RwLock<TranspositionTableBase<Transposition>>);
+pub struct TranspositionTable {
+    buckets: Box<[Bucket]>,
+    generation: AtomicU8,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    overwrites: AtomicU64,
+}
 
 impl TranspositionTable {
     /// Size of a single [`Transposition`] within the [`TranspositionTable`] in bytes.
-    pub const ITEM_SIZE: usize = TranspositionTableBase::<Transposition>::ITEM_SIZE;
+    pub const ITEM_SIZE: usize = size_of::<Entry>();
 
     /// Constructs a [`TranspositionTable`] that can hold
     /// a specified number of transpositions.
     ///
+    /// The capacity is rounded down to a whole number of buckets,
+    /// but the table will always have at least one bucket.
+    ///
     /// # Panics
     /// - Panics if `capacity` is zero.
     pub fn new(capacity: usize) -> Self {
-        Self(RwLock::new(TranspositionTableBase::new(capacity)))
+        assert!(capacity > 0, "must be able to hold at least one item!");
+        let bucket_count = (capacity / BUCKET_LEN).max(1);
+        let buckets = (0..bucket_count).map(|_| Bucket::default()).collect();
+        Self {
+            buckets,
+            generation: AtomicU8::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            overwrites: AtomicU64::new(0),
+        }
+    }
+    /// Returns the maximum number of [`Transposition`]s this
+    /// table can hold at the same time.
+    pub fn capacity(&self) -> usize {
+        self.buckets.len() * BUCKET_LEN
     }
-    // /// Returns the maximum number of [`Transposition`]s this
-    // /// table can hold at the same time.
-    // pub fn capacity(&self) -> usize {
-    //     self.0.read().capacity()
-    // }
     /// Returns the [`Transposition`] with the exactly matching hash
     /// or `None` if one is not available.
     pub fn get(&self, hash: NonZeroU64) -> Option<Transposition> {
-        self.0
-            .read()
-            .get(hash)
-            .filter(|item| item.is_exact())
-            .map(|item| *item)
+        self.bucket(hash)
+            .0
+            .iter()
+            .map(Entry::load)
+            .find(|slot| slot.has_key(hash))
+            .and_then(|slot| slot.transposition())
     }
-    /// Saves the [`Transposition`] to the table and returns the repaced [`Transposition`].
+    /// Saves the [`Transposition`] to the table.
+    ///
+    /// Transposition with the same hash is always replaced, unless it is
+    /// from the current search and is much deeper than the new one.
+    /// Otherwise, the least valuable transposition in the bucket is replaced.
+    ///
+    /// Returns `true` if a transposition of a different position was replaced.
+    pub fn insert(&self, hash: NonZeroU64, value: Transposition) -> bool {
+        const DEPTH_MARGIN: u64 = 2;
+        const AGE_WEIGHT: i32 = 8;
+
+        let generation = self.generation();
+        let entries = &self.bucket(hash).0;
+        let mut victim = &entries[0];
+        let mut victim_worth = i32::MAX;
+        for entry in entries {
+            let slot = entry.load();
+            if slot.has_key(hash) {
+                let is_deeper = slot.depth() as u64 > value.depth + DEPTH_MARGIN;
+                if is_deeper && slot.generation() == generation && value.bound != Bound::Exact {
+                    return false;
+                }
+                victim = entry;
+                victim_worth = i32::MIN;
+                break;
+            }
+            if slot.is_empty() {
+                victim = entry;
+                victim_worth = i32::MIN;
+                break;
+            }
+            let age = generation.wrapping_sub(slot.generation()) % GENERATION_COUNT;
+            let worth = slot.depth() as i32 - AGE_WEIGHT * age as i32;
+            if worth < victim_worth {
+                victim = entry;
+                victim_worth = worth;
+            }
+        }
+        victim.store(hash, value, generation);
+        victim_worth != i32::MIN
+    }
+    /// Tells the table that a new search has started.
+    ///
+    /// Transpositions saved during the previous searches will be
+    /// replaced more eagerly.
     ///
-    /// This will overwrite the [`Transposition`] with the
-    /// clashing hash if one exists.
-    pub fn insert(&self, hash: NonZeroU64, value: Transposition) -> Option<Transposition> {
-        self.0.write().insert(hash, value)
-    }
-    // /// Inserts the new value or replaces the old one if the predicate returns `true`.
-    // pub fn insert_or_replace_if<P>(
-    //     &self,
-    //     hash: NonZeroU64,
-    //     value: Transposition,
-    //     pred: P,
-    // ) -> Option<Transposition>
-    // where
-    //     P: FnOnce(&Transposition) -> bool,
-    // {
-    //     let mut read = self.0.upgradable_read();
-    //     if read.get(hash).is_none_or(|item| pred(item.get())) {
-    //         read.with_upgraded(|cache| cache.insert(hash, value))
-    //     } else {
-    //         None
-    //     }
-    // }
+    /// This also resets the usage statistics.
+    pub fn new_search(&self) {
+        self.generation.fetch_add(1, Ordering::Relaxed);
+        self.reset_usage();
+    }
     /// Clears all saved [`Transposition`]s.
+    ///
+    /// This also resets the usage statistics.
     pub fn clear(&self) {
-        self.0.write().clear();
+        for bucket in &self.buckets {
+            for entry in &bucket.0 {
+                entry.clear();
+            }
+        }
+        self.reset_usage();
     }
-    /// Resize the transposition table.
-    ///
-    /// Calling this will also have the same effect as [`Self::clear`].
-    pub fn resize(&self, new_capacity: usize) {
-        *self.0.write() = TranspositionTableBase::new(new_capacity);
+    /// Adds the usage counters of a thread to the table's statistics.
+    pub fn record_usage(&self, usage: TableUsage) {
+        self.hits.fetch_add(usage.hits, Ordering::Relaxed);
+        self.misses.fetch_add(usage.misses, Ordering::Relaxed);
+        self.overwrites
+            .fetch_add(usage.overwrites, Ordering::Relaxed);
+    }
+    /// Returns the usage statistics since the last call to
+    /// [`Self::new_search`] or [`Self::clear`].
+    pub fn stats(&self) -> HashStats {
+        const SAMPLE_LEN: usize = 1000;
+
+        let generation = self.generation();
+        let sample = self
+            .buckets
+            .iter()
+            .flat_map(|bucket| &bucket.0)
+            .take(SAMPLE_LEN)
+            .map(Entry::load);
+        let mut sampled = 0;
+        let mut occupied = 0;
+        for slot in sample {
+            sampled += 1;
+            if !slot.is_empty() && slot.generation() == generation {
+                occupied += 1;
+            }
+        }
+
+        HashStats {
+            capacity: self.capacity(),
+            hashfull: (occupied * 1000 / sampled) as u16,
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            overwrites: self.overwrites.load(Ordering::Relaxed),
+        }
+    }
+    /// Sets all usage counters to zero.
+    fn reset_usage(&self) {
+        self.hits.store(0, Ordering::Relaxed);
+        self.misses.store(0, Ordering::Relaxed);
+        self.overwrites.store(0, Ordering::Relaxed);
+    }
+    /// Returns the generation of the current search.
+    fn generation(&self) -> u8 {
+        self.generation.load(Ordering::Relaxed) % GENERATION_COUNT
+    }
+    /// Returns the bucket for the specified hash.
+    fn bucket(&self, hash: NonZeroU64) -> &Bucket {
+        let index = (hash.get() as u128 * self.buckets.len() as u128) >> 64;
+        &self.buckets[index as usize]
     }
 }
 
 impl fmt::Debug for TranspositionTable {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_tuple("TranspositionTable").finish()
+        f.debug_struct("TranspositionTable")
+            .field("capacity", &self.capacity())
+            .field("generation", &self.generation())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, thread};
+
+    use super::*;
+    use crate::{Game, MoveOrdering};
+
+    /// Returns the legal moves of the initial position.
+    fn moves() -> Vec<ChessMove> {
+        let mut moves = Vec::new();
+        Game::initial_position()
+            .walk()
+            .for_each_legal_child_node(MoveOrdering::MvvLva, |_, chess_move| {
+                moves.push(chess_move)
+            });
+        moves
+    }
+
+    fn key(n: u64) -> NonZeroU64 {
+        NonZeroU64::new(n.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1).unwrap()
+    }
+
+    fn transposition(depth: u64, bound: Bound) -> Transposition {
+        Transposition {
+            best_move: moves()[0],
+            score: Score::Cp(depth as i32),
+            depth,
+            bound,
+        }
+    }
+
+    /// Returns the depths of the transpositions of the keys
+    /// or `None` for the keys that are not in the table.
+    fn depths(tt: &TranspositionTable, keys: &[u64]) -> Vec<Option<u64>> {
+        keys.iter()
+            .map(|&n| tt.get(key(n)).map(|t| t.depth))
+            .collect()
+    }
+
+    #[test]
+    fn test_full_bucket_evicts_shallowest() {
+        // A single bucket, so that all of the keys compete for it.
+        let tt = TranspositionTable::new(BUCKET_LEN);
+        for (n, depth) in [(1, 5), (2, 2), (3, 7), (4, 4)] {
+            assert!(!tt.insert(key(n), transposition(depth, Bound::Exact)));
+        }
+        assert!(tt.insert(key(5), transposition(3, Bound::Exact)));
+        assert_eq!(
+            depths(&tt, &[1, 2, 3, 4, 5]),
+            [Some(5), None, Some(7), Some(4), Some(3)]
+        );
+        assert!(tt.insert(key(6), transposition(9, Bound::Exact)));
+        assert_eq!(
+            depths(&tt, &[1, 3, 4, 5, 6]),
+            [Some(5), Some(7), Some(4), None, Some(9)]
+        );
+    }
+
+    #[test]
+    fn test_old_generations_are_evicted_first() {
+        let tt = TranspositionTable::new(BUCKET_LEN);
+        tt.insert(key(1), transposition(10, Bound::Exact));
+        tt.insert(key(2), transposition(20, Bound::Exact));
+        tt.new_search();
+        tt.insert(key(3), transposition(3, Bound::Exact));
+        tt.insert(key(4), transposition(3, Bound::Exact));
+        // Worth is the depth minus 8 per generation of age: 2, 12, 3, 3.
+        assert!(tt.insert(key(5), transposition(1, Bound::Exact)));
+        assert_eq!(
+            depths(&tt, &[1, 2, 3, 4, 5]),
+            [None, Some(20), Some(3), Some(3), Some(1)]
+        );
+        tt.new_search();
+        // Worth is now 4, 3 - 8, 3 - 8 and 1 - 8.
+        assert!(tt.insert(key(6), transposition(1, Bound::Exact)));
+        assert_eq!(
+            depths(&tt, &[2, 3, 4, 5, 6]),
+            [Some(20), Some(3), Some(3), None, Some(1)]
+        );
+    }
+
+    #[test]
+    fn test_generations_wrap_around() {
+        let tt = TranspositionTable::new(BUCKET_LEN);
+        tt.insert(key(1), transposition(1, Bound::Exact));
+        for _ in 0..GENERATION_COUNT {
+            tt.new_search();
+        }
+        // After a full cycle the entry is from the current generation again.
+        assert_eq!(tt.stats().hashfull, 250);
+        tt.new_search();
+        assert_eq!(tt.stats().hashfull, 0);
+    }
+
+    #[test]
+    fn test_same_position_keeps_deeper_entry() {
+        let tt = TranspositionTable::new(BUCKET_LEN);
+        tt.insert(key(1), transposition(10, Bound::Lower));
+        // Shallow bounds of the current search do not replace deep entries...
+        assert!(!tt.insert(key(1), transposition(4, Bound::Upper)));
+        assert_eq!(depths(&tt, &[1]), [Some(10)]);
+        // ...but the slightly shallower ones and the exact ones do.
+        tt.insert(key(1), transposition(8, Bound::Upper));
+        assert_eq!(depths(&tt, &[1]), [Some(8)]);
+        tt.insert(key(1), transposition(2, Bound::Exact));
+        assert_eq!(depths(&tt, &[1]), [Some(2)]);
+        // Entries from the previous searches are always replaced.
+        tt.insert(key(1), transposition(10, Bound::Lower));
+        tt.new_search();
+        assert!(!tt.insert(key(1), transposition(4, Bound::Upper)));
+        assert_eq!(depths(&tt, &[1]), [Some(4)]);
+    }
+
+    #[test]
+    fn test_concurrent_access_never_mixes_entries() {
+        const THREADS: u64 = 8;
+        const KEYS: u64 = 64;
+        const ROUNDS: u64 = 2000;
+
+        // Everything about the transposition is derived from the key,
+        // so an entry torn by another thread can be recognized.
+        let moves = moves();
+        let expected = move |n: u64| Transposition {
+            best_move: moves[n as usize % moves.len()],
+            score: Score::Cp(n as i32 * 7 - 200),
+            depth: n % 32,
+            bound: [Bound::Exact, Bound::Lower, Bound::Upper][n as usize % 3],
+        };
+        // Fewer buckets than keys, so that the threads fight over them.
+        let tt = Arc::new(TranspositionTable::new(4 * BUCKET_LEN));
+        let handles: Vec<_> = (0..THREADS)
+            .map(|thread| {
+                let tt = tt.clone();
+                let expected = expected.clone();
+                thread::spawn(move || {
+                    for round in 0..ROUNDS {
+                        let n = (thread * 31 + round * 17) % KEYS;
+                        if (thread + round) % 2 == 0 {
+                            tt.insert(key(n), expected(n));
+                        }
+                        let Some(t) = tt.get(key(n)) else {
+                            continue;
+                        };
+                        let e = expected(n);
+                        assert_eq!(t.best_move, e.best_move);
+                        assert_eq!(t.score, e.score);
+                        assert_eq!(t.depth, e.depth);
+                        assert_eq!(t.bound, e.bound);
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
     }
 }
@@ -1,15 +1,21 @@
-use std::{sync::Arc, thread, time::Instant};
+use std::{
+    mem,
+    sync::{Arc, LazyLock},
+    thread,
+    time::Instant,
+};
 
 use crossbeam::channel::{Receiver, Sender};
-use either::Either;
 
 use crate::{
+    eval::{material_value, Evaluator, EvaluatorFactory},
     search::{
         scheduler::SearchResult,
         signals::{WorkerSignaler, WorkerSignalerMaster},
-        transposition::{Transposition, TranspositionTable},
+        transposition::{Bound, TableUsage, Transposition, TranspositionTable},
     },
-    GameEnding, GameTreeWalker, MoveOrdering, Piece, Score, SearchRequest, ServerResponse,
+    BitBoard, ChessMove, Color, Game, GameTreeWalker, MoveOrdering, Piece, Pv, Rank, Score,
+    SearchRequest, SearchResponse, Window,
 };
 
 /// A search job to be computed by the [`Worker`].
@@ -17,9 +23,21 @@ use crate::{
 pub struct Job {
     /// The job's contents.
     pub request: SearchRequest,
-    /// [`ServerResponse::batch_index`] for the corresponding
+    /// [`SearchResponse::batch_index`] for the corresponding
     /// result must be the same as this.
     pub batch_index: usize,
+    /// Transposition table to use for the job.
+    pub tt: Arc<TranspositionTable>,
+    /// Contempt of the white player (see [`ServerCommand::SetContempt`]).
+    ///
+    /// [`ServerCommand::SetContempt`]: crate::ServerCommand::SetContempt
+    pub contempt: i32,
+    /// Identifier of the search the job belongs to, which changes
+    /// on [`ServerCommand::NewSearch`] and [`ServerCommand::ClearHash`].
+    ///
+    /// [`ServerCommand::NewSearch`]: crate::ServerCommand::NewSearch
+    /// [`ServerCommand::ClearHash`]: crate::ServerCommand::ClearHash
+    pub search_id: u64,
 }
 
 /// Manages a group of worker threads coordinated by signaler.
@@ -29,8 +47,8 @@ pub struct Job {
 pub struct WorkerGroup {
     signaler: Option<WorkerSignalerMaster>,
     job_recv: Receiver<Job>,
-    res_send: Sender<ServerResponse>,
-    tt: Arc<TranspositionTable>,
+    res_send: Sender<SearchResponse>,
+    evaluator: EvaluatorFactory,
 }
 
 impl WorkerGroup {
@@ -38,14 +56,14 @@ impl WorkerGroup {
     pub fn new(
         worker_count: usize,
         job_recv: Receiver<Job>,
-        res_send: Sender<ServerResponse>,
-        tt: Arc<TranspositionTable>,
+        res_send: Sender<SearchResponse>,
+        evaluator: EvaluatorFactory,
     ) -> Self {
         let mut res = Self {
             signaler: None,
             job_recv,
             res_send,
-            tt,
+            evaluator,
         };
         res.spawn_workers(worker_count);
         res
@@ -64,6 +82,14 @@ impl WorkerGroup {
         self.clear();
         self.spawn_workers(new_worker_count);
     }
+    /// Tells all of the current worker threads to quit, then spawns
+    /// the same number of new threads using the specified evaluator.
+    pub fn set_evaluator(&mut self, evaluator: EvaluatorFactory) {
+        let worker_count = self.signaler().worker_count();
+        self.evaluator = evaluator;
+        self.clear();
+        self.spawn_workers(worker_count);
+    }
     fn clear(&mut self) {
         if let Some(signaler) = self.signaler.take() {
             signaler.quit();
@@ -77,16 +103,8 @@ impl WorkerGroup {
             let signaler = master.create_signaler();
             let job_recv = self.job_recv.clone();
             let res_send = self.res_send.clone();
-            let tt = self.tt.clone();
-            thread::spawn(|| {
-                Worker {
-                    signaler,
-                    job_recv,
-                    res_send,
-                    tt,
-                }
-                .run()
-            });
+            let evaluator = self.evaluator;
+            thread::spawn(move || Worker::new(signaler, job_recv, res_send, evaluator()).run());
         }
 
         self.signaler = Some(master);
@@ -99,6 +117,54 @@ impl Drop for WorkerGroup {
     }
 }
 
+/// Value of a queen in centi-pawns.
+const QUEEN_VALUE: i32 = material_value(Piece::Queen).mg;
+/// Safety margin for the delta pruning in centi-pawns.
+const DELTA_MARGIN: i32 = 200;
+/// Smallest depth at which the null move pruning is tried.
+const NULL_MOVE_DEPTH: u64 = 3;
+/// Depth reduction of the null move search in addition to the null move itself.
+const NULL_MOVE_REDUCTION: u64 = 2;
+/// The null move reduction grows by one every this many plies of depth.
+const NULL_MOVE_DEPTH_STEP: u64 = 4;
+/// The null move reduction grows by one every this many centi-pawns
+/// the static evaluation exceeds beta by (at most by [`NULL_MOVE_EVAL_MAX`]).
+const NULL_MOVE_EVAL_STEP: i32 = 200;
+/// Largest increase of the null move reduction due to the static evaluation.
+const NULL_MOVE_EVAL_MAX: u64 = 2;
+/// Smallest depth at which the null move cutoffs are verified
+/// by a reduced search without the null moves.
+const NULL_MOVE_VERIFICATION_DEPTH: u64 = 8;
+/// Smallest depth at which the late move reductions are applied.
+const LMR_DEPTH: u64 = 3;
+/// Number of moves that are never reduced by the late move reductions.
+const LMR_MOVES: usize = 3;
+/// Late move reduction of any move at any depth above one.
+const LMR_BASE: f64 = 0.75;
+/// The late move reduction is `ln(depth) * ln(move number)` divided by this.
+const LMR_DIVISOR: f64 = 2.25;
+/// The late move reduction decreases by one every this many points of history.
+const LMR_HISTORY_STEP: i32 = HISTORY_MAX / 4;
+/// Largest depth at which the late move pruning is applied.
+const LMP_DEPTH: u64 = 3;
+/// Number of moves that are never pruned by the late move pruning
+/// in addition to the square of the depth.
+const LMP_MOVES: usize = 3;
+/// Largest value of the history scores (see [`Worker::history`]).
+const HISTORY_MAX: i32 = 1 << 14;
+
+/// Late move reductions indexed by the depth and the move number.
+static REDUCTIONS: LazyLock<[[u8; 64]; 64]> = LazyLock::new(|| {
+    let mut reductions = [[0; 64]; 64];
+    for (depth, row) in reductions.iter_mut().enumerate().skip(1) {
+        for (number, reduction) in row.iter_mut().enumerate().skip(1) {
+            let log = (depth as f64).ln() * (number as f64).ln();
+            *reduction = (LMR_BASE + log / LMR_DIVISOR) as u8;
+        }
+    }
+    reductions
+});
+
 #[derive(Debug, Clone, Copy)]
 struct SearchConstraints {
     pub nodes_max: Option<u64>,
@@ -114,36 +180,119 @@ impl SearchConstraints {
     }
 }
 
+/// Result of the quiescence search.
+///
+/// Unlike [`SearchResult`] it has no principal variation, since it is
+/// returned from the most numerous nodes and the captures that follow
+/// the horizon are not worth reporting.
+#[derive(Debug, Clone, Copy)]
+struct QuiescenceResult {
+    score: Score,
+    nodes: u64,
+    seldepth: u64,
+    best_move: Option<ChessMove>,
+    is_canceled: bool,
+}
+
+impl From<QuiescenceResult> for SearchResult {
+    fn from(result: QuiescenceResult) -> Self {
+        SearchResult {
+            score: result.score,
+            nodes: result.nodes,
+            seldepth: result.seldepth,
+            best_move: result.best_move,
+            pv: result
+                .best_move
+                .map_or(Pv::EMPTY, |chess_move| Pv::new(chess_move, &Pv::EMPTY)),
+            is_canceled: result.is_canceled,
+        }
+    }
+}
+
 #[derive(Debug)]
 struct Worker {
     signaler: WorkerSignaler,
     job_recv: Receiver<Job>,
-    res_send: Sender<ServerResponse>,
-    tt: Arc<TranspositionTable>,
+    res_send: Sender<SearchResponse>,
+    /// Transposition table of the current job.
+    tt: Option<Arc<TranspositionTable>>,
+    /// Transposition table usage during the current job.
+    tt_usage: TableUsage,
+    /// Contempt of the white player during the current job.
+    contempt: i32,
+    /// [Killer moves] for each ply of the current search.
+    ///
+    /// [Killer moves]: https://www.chessprogramming.org/Killer_Heuristic
+    killers: Vec<[Option<ChessMove>; 2]>,
+    /// Ply of the node whose null move cutoff is being verified.
+    ///
+    /// Null moves are not made at this ply and the next one, and the
+    /// reduced result of the node itself is not stored in the table.
+    verification_ply: Option<usize>,
+    /// [History heuristic] scores of the quiet moves indexed
+    /// by the color, the origin and the destination squares.
+    ///
+    /// The scores are shared by all jobs of the same search.
+    ///
+    /// [History heuristic]: https://www.chessprogramming.org/History_Heuristic
+    history: Box<[[[i32; 64]; 64]; 2]>,
+    /// Identifier of the search the history scores were collected in.
+    history_search_id: Option<u64>,
+    /// Static evaluation of the quiet positions.
+    evaluator: Box<dyn Evaluator>,
 }
 
 impl Worker {
+    fn new(
+        signaler: WorkerSignaler,
+        job_recv: Receiver<Job>,
+        res_send: Sender<SearchResponse>,
+        evaluator: Box<dyn Evaluator>,
+    ) -> Self {
+        Self {
+            signaler,
+            job_recv,
+            res_send,
+            tt: None,
+            tt_usage: TableUsage::default(),
+            contempt: 0,
+            killers: Vec::new(),
+            verification_ply: None,
+            history: Box::new([[[0; 64]; 64]; 2]),
+            history_search_id: None,
+            evaluator,
+        }
+    }
     fn run(&mut self) {
         loop {
             self.signaler.wakeup();
 
             while let Ok(job) = self.job_recv.try_recv() {
                 let mut game = job.request.game;
-                let worst_score = Score::ending(GameEnding::Checkmate);
+                self.tt = Some(job.tt);
+                self.contempt = job.contempt;
+                self.killers.clear();
+                if self.history_search_id != Some(job.search_id) {
+                    self.history.fill([[0; 64]; 64]);
+                    self.history_search_id = Some(job.search_id);
+                }
                 let result = self.search(
                     &mut game.walk(),
                     job.request.depth,
+                    0,
                     SearchConstraints {
                         nodes_max: job.request.nodes,
                         deadline: job.request.deadline,
                     },
-                    worst_score,
-                    worst_score.prev(),
+                    job.request.window,
+                    true,
                 );
-                let result = ServerResponse {
+                let result = SearchResponse {
                     result,
                     batch_index: job.batch_index,
                 };
+                let tt = self.tt.take().unwrap();
+                tt.record_usage(mem::take(&mut self.tt_usage));
                 self.res_send.send(result).unwrap();
             }
 
@@ -158,137 +307,708 @@ impl Worker {
         &mut self,
         node: &mut GameTreeWalker,
         depth: u64,
+        ply: usize,
         constraints: SearchConstraints,
-        mut alpha: Score,
-        beta: Score,
+        window: Window,
+        is_pv: bool,
     ) -> SearchResult {
+        let Window { mut alpha, beta } = window;
         if self.signaler.should_stop() || constraints.time_fails() {
-            return self.evaluate(node, true);
+            return self.canceled(node);
+        }
+        // Repeating a position from the search tree is already a draw, since
+        // a player that could improve on it could have done so the last time.
+        // The positions played before the search have to repeat twice.
+        if let Some(ending) = node.check_draw(ply) {
+            return SearchResult {
+                best_move: None,
+                pv: Pv::EMPTY,
+                score: Score::ending(ending, self.contempt(node.game())),
+                nodes: 1,
+                seldepth: 0,
+                is_canceled: false,
+            };
         }
 
         let position = node.game();
         let hash = position.zobrist();
-        if let Some(t) = self.tt.get(hash) {
+        let transposition = self.tt().get(hash);
+        match transposition {
+            Some(_) => self.tt_usage.hits += 1,
+            None => self.tt_usage.misses += 1,
+        }
+        if let Some(t) = transposition {
             'probe_hash: {
-                if position.is_move_pseudo_legal(t.best_move) {
+                if !position.is_move_pseudo_legal(t.best_move) {
+                    // Most likely a hash collision.
                     break 'probe_hash;
                 }
                 if t.depth < depth {
-                    // TODO: should optimize for hash move here.
+                    break 'probe_hash;
+                }
+                // Mate distances are always counted from the position itself,
+                // so the score can be reused no matter how far from the root
+                // the position occurs.
+                let is_cutoff = match t.bound {
+                    Bound::Exact => true,
+                    Bound::Lower => t.score >= beta,
+                    Bound::Upper => t.score <= alpha,
+                };
+                if !is_cutoff {
                     break 'probe_hash;
                 }
                 return SearchResult {
                     best_move: Some(t.best_move),
+                    pv: Pv::new(t.best_move, &Pv::EMPTY),
                     score: t.score,
                     nodes: 1,
+                    seldepth: 0,
                     is_canceled: false,
                 };
             }
         }
 
         if depth == 0 {
-            return self.quiescence(node, alpha, beta);
+            return self.quiescence(node, alpha, beta).into();
         }
 
-        let mut best_move = None;
-        let mut best_score = None;
+        let mut seldepth = 0;
         let mut nodes = 1;
-        let mut is_canceled = false;
-        let maybe_ending =
-            node.for_each_legal_child_node(MoveOrdering::MvvLva, |node, chess_move| {
-                let result = self.search(node, depth - 1, constraints, alpha, beta);
+        if let Some(reduction) = self.null_move_reduction(node, depth, ply, beta, is_pv) {
+            // Null move pruning: if the position is still too good for
+            // the opponent after passing the turn, it will be avoided.
+            let null_depth = depth.saturating_sub(reduction + 1);
+            let window = Window {
+                alpha: beta.add_cp(-1),
+                beta,
+            };
+            let result = node.map_null_move(|node| {
+                let window = window.next();
+                self.search(node, null_depth, ply + 1, constraints, window, false)
+            });
+            nodes += result.nodes;
+            seldepth = result.seldepth + 1;
+            let mut score = result.score.prev();
+            let mut is_cutoff = score >= beta && !result.is_canceled;
+            if is_cutoff && depth >= NULL_MOVE_VERIFICATION_DEPTH {
+                // Zugzwang can make the null move look better than any real
+                // move, so the deep cutoffs are confirmed without null moves
+                // close to this node.
+                let verification_ply = self.verification_ply.replace(ply);
+                let result = self.search(node, null_depth, ply, constraints, window, false);
+                self.verification_ply = verification_ply;
                 nodes += result.nodes;
-                if best_score.is_none_or(|score| result.score.prev() > score) {
-                    best_score = Some(result.score);
-                    best_move = Some(chess_move);
+                seldepth = seldepth.max(result.seldepth);
+                is_cutoff = result.score >= beta && !result.is_canceled;
+            }
+            if is_cutoff {
+                // Mates found after passing the turn are not proven.
+                if let Score::Mating(_) = score {
+                    score = beta;
                 }
+                return SearchResult {
+                    best_move: None,
+                    pv: Pv::EMPTY,
+                    score,
+                    nodes,
+                    seldepth,
+                    is_canceled: false,
+                };
+            }
+        }
 
-                if result.score > alpha {
-                    alpha = result.score;
-                }
+        let alpha_orig = alpha;
+        // The moves of the root and of the PV nodes are never reduced nor
+        // pruned, since the search depends on their scores being accurate.
+        // The PV nodes are flagged by the caller rather than inferred from
+        // the window, since the window of a PV node can be a null window.
+        let is_reducible = !is_pv && ply > 0;
+        let is_check = node.game().is_check();
+        let turn = node.game().turn();
+        let mut best_move = None;
+        let mut best_score = None;
+        let mut pv = Pv::EMPTY;
+        let mut is_canceled = false;
+        let mut move_index = 0;
+        let ordering = MoveOrdering::Staged {
+            hash_move: transposition.map(|t| t.best_move),
+            killers: self.killers.get(ply).copied().unwrap_or_default(),
+        };
+        let maybe_ending = node.for_each_legal_child_node(ordering, |node, chess_move| {
+            let hint = chess_move.hint();
+            let is_quiet = !hint.is_capture() && !hint.is_promotion();
+            let gives_check = node.game().is_check();
+            move_index += 1;
 
-                if result.score >= beta {
-                    node.exhaust_moves();
-                    return;
-                }
+            // Late move pruning: quiet moves that come late in the ordering
+            // are unlikely to matter close to the horizon, unless the player
+            // is getting mated anyway.
+            if is_quiet
+                && is_reducible
+                && !is_check
+                && !gives_check
+                && depth <= LMP_DEPTH
+                && move_index > LMP_MOVES + (depth * depth) as usize
+                && best_score.is_some_and(|best| !matches!(best, Score::Mated(_)))
+            {
+                return;
+            }
 
-                if constraints.nodes_fail(nodes)
-                    || constraints.time_fails()
-                    || self.signaler.should_stop()
-                {
-                    is_canceled = true;
-                    node.exhaust_moves();
+            // Principal variation search: the first move is expected to be
+            // the best one, so the others are searched with a null window
+            // that only proves them worse, and re-searched if they are not.
+            let mut result = None;
+            if best_score.is_some() {
+                // Once the window of a PV node is down to a null window, no
+                // move can be re-searched, so their null window searches are
+                // final and must be as accurate as the PV searches.
+                let is_scout_pv = is_pv && Window::null(alpha).beta == beta;
+                let window = Window::null(alpha).next();
+                // Late move reductions: the quiet moves that come late in the
+                // ordering are searched less deep first, and only searched
+                // to the full depth if they turn out to be good.
+                let is_late = is_quiet && depth >= LMR_DEPTH && move_index > LMR_MOVES;
+                let reduction = match is_late && is_reducible {
+                    true => self.late_move_reduction(
+                        depth,
+                        move_index,
+                        is_check || gives_check,
+                        turn,
+                        chess_move,
+                    ),
+                    false => 0,
+                };
+                let mut null_result = self.search(
+                    node,
+                    depth - 1 - reduction,
+                    ply + 1,
+                    constraints,
+                    window,
+                    is_scout_pv,
+                );
+                // Mates found by the reduced search may be shorter or longer
+                // at the full depth, so they are always verified.
+                let is_mate = !matches!(null_result.score, Score::Cp(_));
+                let is_good = null_result.score.prev() > alpha || is_mate;
+                if reduction > 0 && is_good && !null_result.is_canceled {
+                    nodes += null_result.nodes;
+                    null_result =
+                        self.search(node, depth - 1, ply + 1, constraints, window, is_scout_pv);
                 }
+                let score = null_result.score.prev();
+                if score <= alpha || score >= beta || null_result.is_canceled {
+                    result = Some(null_result);
+                } else {
+                    nodes += null_result.nodes;
+                }
+            }
+            let result = result.unwrap_or_else(|| {
+                self.search(
+                    node,
+                    depth - 1,
+                    ply + 1,
+                    constraints,
+                    Window { alpha, beta }.next(),
+                    is_pv,
+                )
             });
+            let score = result.score.prev();
+            nodes += result.nodes;
+            seldepth = seldepth.max(result.seldepth + 1);
+            if best_score.is_none_or(|best| score > best) {
+                best_score = Some(score);
+                best_move = Some(chess_move);
+                pv = Pv::new(chess_move, &result.pv);
+            }
+
+            if score > alpha {
+                alpha = score;
+            }
+
+            if score >= beta {
+                if is_quiet {
+                    self.store_killer(ply, chess_move);
+                    self.store_history(turn, chess_move, depth);
+                }
+                node.exhaust_moves();
+                return;
+            }
+
+            if constraints.nodes_fail(nodes)
+                || constraints.time_fails()
+                || self.signaler.should_stop()
+            {
+                is_canceled = true;
+                node.exhaust_moves();
+            }
+        });
         let score = match maybe_ending {
-            Some(ending) => Score::ending(ending),
+            Some(ending) => Score::ending(ending, self.contempt(node.game())),
             None => {
                 let best_move = best_move.unwrap();
                 let score = best_score.unwrap();
-                self.tt.insert(
-                    hash,
-                    Transposition {
+                // The verification search is shallower than the node,
+                // so its result must not replace the real one.
+                if !is_canceled && self.verification_ply != Some(ply) {
+                    let bound = if score >= beta {
+                        Bound::Lower
+                    } else if score > alpha_orig {
+                        Bound::Exact
+                    } else {
+                        Bound::Upper
+                    };
+                    let transposition = Transposition {
                         best_move,
                         depth,
                         score,
-                    },
-                );
+                        bound,
+                    };
+                    if self.tt().insert(hash, transposition) {
+                        self.tt_usage.overwrites += 1;
+                    }
+                }
                 score
             }
         };
 
         SearchResult {
             best_move,
+            pv,
             score,
             nodes,
+            seldepth,
             is_canceled,
         }
     }
-    fn quiescence(&mut self, node: &mut GameTreeWalker, alpha: Score, beta: Score) -> SearchResult {
+    /// Returns the depth reduction of the null move search (in addition
+    /// to the null move itself) or `None` if the null move pruning
+    /// should not be tried in the position.
+    ///
+    /// Null moves are only made in the non-PV nodes and never in check
+    /// or twice in a row. They are also avoided when the playing player has
+    /// only pawns, since zugzwang is common in such endgames.
+    fn null_move_reduction(
+        &mut self,
+        node: &GameTreeWalker,
+        depth: u64,
+        ply: usize,
+        beta: Score,
+        is_pv: bool,
+    ) -> Option<u64> {
+        let position = node.game();
+        let board = position.board();
+        let pieces = board.get_color(position.turn())
+            & !(board.get_piece(Piece::Pawn) | board.get_piece(Piece::King));
+        let Score::Cp(beta_cp) = beta else {
+            return None;
+        };
+        if depth < NULL_MOVE_DEPTH
+            || self.verification_ply.is_some_and(|v| ply < v + 2)
+            || is_pv
+            || pieces.is_empty()
+            || position.is_check()
+            || position.was_null_move()
+        {
+            return None;
+        }
+
+        let Score::Cp(eval) = self.evaluate(position) else {
+            return None;
+        };
+        let excess = eval.saturating_sub(beta_cp);
+        if excess < 0 {
+            return None;
+        }
+        let eval_reduction = (excess / NULL_MOVE_EVAL_STEP) as u64;
+        Some(
+            NULL_MOVE_REDUCTION
+                + depth / NULL_MOVE_DEPTH_STEP
+                + eval_reduction.min(NULL_MOVE_EVAL_MAX),
+        )
+    }
+    /// Searches captures and promotions until the position becomes quiet.
+    ///
+    /// When in check all evasions are searched instead and standing pat is
+    /// not allowed, so that the mates are not overlooked.
+    fn quiescence(
+        &mut self,
+        node: &mut GameTreeWalker,
+        mut alpha: Score,
+        beta: Score,
+    ) -> QuiescenceResult {
         if self.signaler.should_stop() {
-            return self.evaluate(node, true);
+            return QuiescenceResult {
+                score: self.evaluate(node.game()),
+                nodes: 1,
+                seldepth: 0,
+                best_move: None,
+                is_canceled: true,
+            };
         }
 
-        _ = (alpha, beta);
+        let is_check = node.game().is_check();
+        let mut best_move = None;
+        let mut best_score = None;
+        let mut seldepth = 0;
+        let mut nodes = 1;
 
-        // TODO: implement quiescence search.
-        self.evaluate(node, false)
-    }
-    fn evaluate(&mut self, node: &mut GameTreeWalker, is_canceled: bool) -> SearchResult {
-        let nodes = 1;
-        let any_move = match node.check_ending() {
-            Either::Left(chess_move) => chess_move,
-            Either::Right(ending) => {
-                return SearchResult {
+        let stand_pat = (!is_check).then(|| self.evaluate(node.game()));
+        if let Some(stand_pat) = stand_pat {
+            if stand_pat >= beta {
+                return QuiescenceResult {
+                    best_move: None,
+                    score: stand_pat,
+                    nodes,
+                    seldepth: 0,
+                    is_canceled: false,
+                };
+            }
+            // Delta pruning: even winning the queen will not get us back to alpha,
+            // unless a pawn is about to promote on top of that.
+            let position = node.game();
+            let promoters = position
+                .board()
+                .get_color_piece(position.turn(), Piece::Pawn)
+                & BitBoard::from(position.turn().mirror_rank(Rank::R7));
+            if promoters.is_empty() && stand_pat.add_cp(QUEEN_VALUE + DELTA_MARGIN) < alpha {
+                return QuiescenceResult {
                     best_move: None,
-                    score: Score::ending(ending),
+                    score: stand_pat,
                     nodes,
-                    is_canceled,
+                    seldepth: 0,
+                    is_canceled: false,
+                };
+            }
+            if stand_pat > alpha {
+                alpha = stand_pat;
+            }
+            best_score = Some(stand_pat);
+        }
+
+        let mut visit = |node: &mut GameTreeWalker, chess_move: ChessMove| {
+            // Delta pruning: skip the captures that can not raise alpha
+            // even with a safety margin. Promotions and en passant
+            // are always searched.
+            let captured = node.game().last_capture();
+            if let (Some(stand_pat), Some(captured)) = (stand_pat, captured) {
+                let gain = material_value(captured).mg + DELTA_MARGIN;
+                if !chess_move.hint().is_promotion() && stand_pat.add_cp(gain) <= alpha {
+                    return;
                 }
             }
+
+            let result = self.quiescence(node, beta.next(), alpha.next());
+            let score = result.score.prev();
+            nodes += result.nodes;
+            seldepth = seldepth.max(result.seldepth + 1);
+            if best_score.is_none_or(|best| score > best) {
+                best_score = Some(score);
+                best_move = Some(chess_move);
+            }
+
+            if score > alpha {
+                alpha = score;
+            }
+
+            if score >= beta {
+                node.exhaust_moves();
+            }
         };
 
-        let position = node.game();
-        let board = position.board();
-        let player = board.get_color(position.turn());
-        let queens = board.get_piece(Piece::Queen);
-        let rooks = board.get_piece(Piece::Rook);
-        let bishops = board.get_piece(Piece::Bishop);
-        let knights = board.get_piece(Piece::Knight);
-        let pawns = board.get_piece(Piece::Pawn);
-        let q_score = (queens & player).count() as i32 - (queens & !player).count() as i32;
-        let r_score = (rooks & player).count() as i32 - (rooks & !player).count() as i32;
-        let b_score = (bishops & player).count() as i32 - (bishops & !player).count() as i32;
-        let n_score = (knights & player).count() as i32 - (knights & !player).count() as i32;
-        let p_score = (pawns & player).count() as i32 - (pawns & !player).count() as i32;
-        let p = p_score + (n_score + b_score) * 3 + r_score * 5 + q_score * 9;
-        let score = Score::Cp(p * 100);
+        let score = if is_check {
+            match node.for_each_legal_child_node(MoveOrdering::MvvLva, &mut visit) {
+                Some(ending) => Score::ending(ending, self.contempt(node.game())),
+                None => best_score.unwrap(),
+            }
+        } else {
+            node.for_each_legal_capture_node(MoveOrdering::MvvLva, &mut visit);
+            best_score.unwrap()
+        };
 
-        SearchResult {
-            best_move: Some(any_move),
+        QuiescenceResult {
+            best_move,
             score,
             nodes,
-            is_canceled,
+            seldepth,
+            is_canceled: false,
+        }
+    }
+    /// Returns the transposition table of the current job.
+    fn tt(&self) -> &TranspositionTable {
+        self.tt.as_deref().expect("worker has no job")
+    }
+    /// Returns the contempt of the player who is about to make a turn.
+    fn contempt(&self, position: &Game) -> i32 {
+        match position.turn() {
+            Color::White => self.contempt,
+            Color::Black => self.contempt.saturating_neg(),
+        }
+    }
+    /// Remembers a quiet move that caused a cutoff at this ply.
+    fn store_killer(&mut self, ply: usize, chess_move: ChessMove) {
+        if self.killers.len() <= ply {
+            self.killers.resize(ply + 1, [None; 2]);
+        }
+        let killers = &mut self.killers[ply];
+        if killers[0] != Some(chess_move) {
+            killers[1] = killers[0];
+            killers[0] = Some(chess_move);
+        }
+    }
+    /// Returns the history score of the quiet move.
+    fn history(&self, turn: Color, chess_move: ChessMove) -> i32 {
+        let from = chess_move.orig_square() as usize;
+        let to = chess_move.dest_square() as usize;
+        self.history[turn as usize][from][to]
+    }
+    /// Rewards a quiet move that caused a cutoff at the depth.
+    fn store_history(&mut self, turn: Color, chess_move: ChessMove, depth: u64) {
+        let from = chess_move.orig_square() as usize;
+        let to = chess_move.dest_square() as usize;
+        let bonus = (depth * depth).min(HISTORY_MAX as u64) as i32;
+        let history = &mut self.history[turn as usize][from][to];
+        // The scores of the frequent cutoffs approach the maximum, so that
+        // the old cutoffs are gradually forgotten.
+        *history += bonus - *history * bonus / HISTORY_MAX;
+    }
+    /// Returns the late move reduction of a quiet move, which is smaller
+    /// for the checks and for the moves with good history.
+    ///
+    /// The reduced search is always at least one ply deep.
+    fn late_move_reduction(
+        &self,
+        depth: u64,
+        move_number: usize,
+        is_check: bool,
+        turn: Color,
+        chess_move: ChessMove,
+    ) -> u64 {
+        let mut reduction = REDUCTIONS[depth.min(63) as usize][move_number.min(63)] as i32;
+        reduction -= is_check as i32;
+        reduction -= self.history(turn, chess_move) / LMR_HISTORY_STEP;
+        reduction.clamp(0, (depth as i32 - 2).max(0)) as u64
+    }
+    /// Returns the result for a search that had to be abruptly stopped.
+    fn canceled(&mut self, node: &GameTreeWalker) -> SearchResult {
+        SearchResult {
+            best_move: None,
+            pv: Pv::EMPTY,
+            score: self.evaluate(node.game()),
+            nodes: 1,
+            seldepth: 0,
+            is_canceled: true,
+        }
+    }
+    /// Returns the static evaluation of the position for the player who is about to make a turn.
+    fn evaluate(&mut self, position: &Game) -> Score {
+        self.evaluator.evaluate(position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossbeam::channel::unbounded;
+
+    use super::*;
+    use crate::{eval::TaperedEvaluator, search::signals::WorkerSignalerMaster, LanMove};
+
+    const FEN: &str = "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1";
+    const MATE_FEN: &str = "k7/8/2K5/8/8/8/8/1R6 w - - 0 1";
+
+    /// Returns a running worker with an empty transposition table.
+    fn worker() -> Worker {
+        let mut master = WorkerSignalerMaster::new(1);
+        let signaler = master.create_signaler();
+        let go = thread::spawn(move || master.go());
+        signaler.wakeup();
+        go.join().unwrap();
+        let (_, job_recv) = unbounded();
+        let (res_send, _) = unbounded();
+        let mut worker = Worker::new(signaler, job_recv, res_send, TaperedEvaluator::boxed());
+        worker.tt = Some(Arc::new(TranspositionTable::new(1024)));
+        worker
+    }
+
+    /// Stores a deep transposition of the position with the first legal move.
+    fn store(worker: &Worker, game: &mut Game, score: Score, bound: Bound) {
+        let best_move = game.walk().check_ending().left().unwrap();
+        let transposition = Transposition {
+            best_move,
+            depth: 8,
+            score,
+            bound,
+        };
+        worker.tt().insert(game.zobrist(), transposition);
+    }
+
+    /// Searches the position as if it was the specified number of plies
+    /// away from the root, either in or out of the principal variation.
+    fn search(worker: &mut Worker, fen: &str, depth: u64, ply: usize, is_pv: bool) -> SearchResult {
+        let mut game = Game::try_from_fen(fen).unwrap();
+        let constraints = SearchConstraints {
+            nodes_max: None,
+            deadline: None,
+        };
+        let window = Window::FULL;
+        worker.search(&mut game.walk(), depth, ply, constraints, window, is_pv)
+    }
+
+    /// Searches the position and returns whether it was cut off by
+    /// the transposition table, along with the score.
+    fn probe(worker: &mut Worker, game: &mut Game, ply: usize, window: Window) -> (bool, Score) {
+        let constraints = SearchConstraints {
+            nodes_max: None,
+            deadline: None,
+        };
+        let result = worker.search(&mut game.walk(), 2, ply, constraints, window, true);
+        (result.nodes == 1, result.score)
+    }
+
+    fn window(alpha: i32, beta: i32) -> Window {
+        Window {
+            alpha: Score::Cp(alpha),
+            beta: Score::Cp(beta),
+        }
+    }
+
+    #[test]
+    fn test_exact_bound_always_cuts_off() {
+        let mut worker = worker();
+        let mut game = Game::try_from_fen(FEN).unwrap();
+        store(&worker, &mut game, Score::Cp(50), Bound::Exact);
+        for window in [window(0, 40), window(0, 100), window(60, 100)] {
+            let probe = probe(&mut worker, &mut game, 0, window);
+            assert_eq!(probe, (true, Score::Cp(50)), "{window:?}");
+        }
+    }
+
+    #[test]
+    fn test_lower_bound_cuts_off_above_window() {
+        let mut worker = worker();
+        let mut game = Game::try_from_fen(FEN).unwrap();
+        store(&worker, &mut game, Score::Cp(50), Bound::Lower);
+        assert_eq!(
+            probe(&mut worker, &mut game, 0, window(0, 40)),
+            (true, Score::Cp(50))
+        );
+        assert_eq!(
+            probe(&mut worker, &mut game, 0, window(0, 50)),
+            (true, Score::Cp(50))
+        );
+        assert!(!probe(&mut worker, &mut game, 0, window(60, 100)).0);
+    }
+
+    #[test]
+    fn test_upper_bound_cuts_off_below_window() {
+        let mut worker = worker();
+        let mut game = Game::try_from_fen(FEN).unwrap();
+        store(&worker, &mut game, Score::Cp(50), Bound::Upper);
+        assert_eq!(
+            probe(&mut worker, &mut game, 0, window(60, 100)),
+            (true, Score::Cp(50))
+        );
+        assert_eq!(
+            probe(&mut worker, &mut game, 0, window(50, 100)),
+            (true, Score::Cp(50))
+        );
+        assert!(!probe(&mut worker, &mut game, 0, window(0, 40)).0);
+    }
+
+    #[test]
+    fn test_mate_score_is_reused_at_any_ply() {
+        let mut worker = worker();
+        let mut game = Game::try_from_fen(MATE_FEN).unwrap();
+        store(&worker, &mut game, Score::Mating(2), Bound::Exact);
+        for ply in [0, 1, 6] {
+            let result = probe(&mut worker, &mut game, ply, Window::FULL);
+            assert_eq!(result, (true, Score::Mating(2)), "ply {ply}");
+        }
+        // A mate bound is only a cutoff when it is outside of the window.
+        store(&worker, &mut game, Score::Mating(2), Bound::Lower);
+        let result = probe(&mut worker, &mut game, 3, window(0, 100));
+        assert_eq!(result, (true, Score::Mating(2)));
+        assert!(!probe(&mut worker, &mut game, 3, Window::FULL).0);
+    }
+
+    #[test]
+    fn test_late_move_pruning_skips_the_root_and_the_pv() {
+        // Only moving the rook away saves it, but all of the rook moves
+        // come after the capture and the pawn moves.
+        let fen = "4k3/8/5p2/4p3/3R4/8/PP5P/4K3 w - - 0 1";
+        let root = search(&mut worker(), fen, 1, 0, true);
+        let pv = search(&mut worker(), fen, 1, 1, true);
+        let inner = search(&mut worker(), fen, 1, 1, false);
+        assert_eq!(pv.score, root.score);
+        assert!(inner.score < root.score.add_cp(-300), "{inner:?} {root:?}");
+    }
+
+    #[test]
+    fn test_late_move_pruning_keeps_checks() {
+        // The rook can escape with a check.
+        let fen = "6k1/8/5p2/4p3/3R4/8/PP5P/4K3 w - - 0 1";
+        let root = search(&mut worker(), fen, 1, 0, true);
+        let inner = search(&mut worker(), fen, 1, 1, false);
+        assert!(inner.score > root.score.add_cp(-100), "{inner:?} {root:?}");
+        let checks = ["d4d8", "d4g4"].map(|lan| lan.parse::<LanMove>().ok());
+        assert!(checks.contains(&inner.best_move.map(|m| m.lan())));
+    }
+
+    #[test]
+    fn test_late_move_reduction_leaves_one_ply() {
+        let worker = worker();
+        let chess_move = Game::initial_position()
+            .walk()
+            .check_ending()
+            .left()
+            .unwrap();
+        for depth in 0..80 {
+            for number in 0..80 {
+                let reduction =
+                    worker.late_move_reduction(depth, number, false, Color::White, chess_move);
+                assert!(reduction <= depth.saturating_sub(2), "{depth} {number}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_late_move_reduction_grows_later() {
+        let worker = worker();
+        let chess_move = Game::initial_position()
+            .walk()
+            .check_ending()
+            .left()
+            .unwrap();
+        let reduction = |depth, number| {
+            worker.late_move_reduction(depth, number, false, Color::White, chess_move)
+        };
+        for depth in LMR_DEPTH..40 {
+            for number in LMR_MOVES + 1..40 {
+                assert!(reduction(depth, number) <= reduction(depth, number + 1));
+                assert!(reduction(depth, number) <= reduction(depth + 1, number));
+            }
+        }
+        assert_eq!(reduction(LMR_DEPTH, LMR_MOVES + 1), 1);
+        assert!(reduction(20, 30) > 2);
+    }
+
+    #[test]
+    fn test_late_move_reduction_is_smaller_for_good_moves() {
+        let mut worker = worker();
+        let chess_move = Game::initial_position()
+            .walk()
+            .check_ending()
+            .left()
+            .unwrap();
+        let quiet = worker.late_move_reduction(20, 30, false, Color::White, chess_move);
+        let check = worker.late_move_reduction(20, 30, true, Color::White, chess_move);
+        assert_eq!(check, quiet - 1);
+        for _ in 0..100 {
+            worker.store_history(Color::White, chess_move, 20);
         }
+        let history = worker.late_move_reduction(20, 30, false, Color::White, chess_move);
+        assert!(history < quiet, "{history} {quiet}");
+        // History of the other player does not count.
+        let other = worker.late_move_reduction(20, 30, false, Color::Black, chess_move);
+        assert_eq!(other, quiet);
     }
 }
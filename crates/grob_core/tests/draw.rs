@@ -0,0 +1,201 @@
+use either::Either;
+use grob_core::{Game, GameEnding, LanMove};
+
+fn ending(fen: &str) -> Option<GameEnding> {
+    let mut game = Game::try_from_fen(fen).expect("Incorrect FEN!");
+    game.walk().check_ending().right()
+}
+
+fn play(game: &mut Game, moves: &[&str]) {
+    for lan_move in moves {
+        let lan_move: LanMove = lan_move.parse().unwrap();
+        let chess_move = game.lan_move(lan_move).unwrap();
+        assert!(game.try_make_move(chess_move));
+    }
+}
+
+mod repetition {
+    use super::*;
+
+    const KNIGHT_DANCE: [&str; 4] = ["g1f3", "g8f6", "f3g1", "f6g8"];
+
+    #[test]
+    fn ends_game_on_third_occurrence() {
+        let mut game = Game::initial_position();
+        play(&mut game, &KNIGHT_DANCE);
+        assert_eq!(game.count_repetitions(), 1);
+        assert!(game.walk().check_ending().is_left());
+
+        play(&mut game, &KNIGHT_DANCE);
+        assert_eq!(game.count_repetitions(), 2);
+        assert_eq!(
+            game.walk().check_ending(),
+            Either::Right(GameEnding::Repetition)
+        );
+    }
+
+    #[test]
+    fn is_a_draw_on_second_occurrence_in_search_tree() {
+        let mut game = Game::initial_position();
+        play(&mut game, &KNIGHT_DANCE);
+        assert_eq!(game.last_repetition(), Some(4));
+        assert_eq!(game.walk().check_draw(4), Some(GameEnding::Repetition));
+        assert_eq!(game.walk().check_draw(3), None);
+    }
+
+    #[test]
+    fn is_reset_by_pawn_moves() {
+        let mut game = Game::initial_position();
+        play(&mut game, &KNIGHT_DANCE);
+        play(&mut game, &["e2e3", "e7e6"]);
+        play(&mut game, &KNIGHT_DANCE);
+        assert_eq!(game.count_repetitions(), 1);
+    }
+
+    const QUEEN_DANCE: [&str; 4] = ["d1d2", "e8e7", "d2d1", "e7e8"];
+
+    #[test]
+    fn is_reset_by_null_moves() {
+        let mut game = Game::initial_position();
+        play(&mut game, &["g1f3"]);
+        game.make_null_move();
+        play(&mut game, &["f3g1"]);
+        game.make_null_move();
+        assert_eq!(game.zobrist(), Game::initial_position().zobrist());
+        assert_eq!(game.count_repetitions(), 0);
+    }
+
+    #[test]
+    fn is_a_draw_in_search() {
+        let mut game = Game::try_from_fen("4k3/8/8/8/8/8/8/3QK3 w - - 0 1").unwrap();
+        play(&mut game, &QUEEN_DANCE);
+        play(&mut game, &QUEEN_DANCE);
+        // Being a queen up does not matter once the position occurs
+        // for the third time.
+        let result = crate::search::search(game, 3);
+        assert_eq!(result.score, grob_core::Score::Cp(0));
+    }
+
+    #[test]
+    fn needs_third_occurrence_before_search() {
+        let mut game = Game::try_from_fen("4k3/8/8/8/8/8/8/3QK3 w - - 0 1").unwrap();
+        play(&mut game, &QUEEN_DANCE);
+        // A single earlier occurrence does not make the position a draw,
+        // nor does repeating it once more in the search.
+        let result = crate::search::search(game, 3);
+        assert!(result.score > grob_core::Score::Cp(500), "{result:?}");
+    }
+}
+
+mod fifty_moves {
+    use super::*;
+
+    #[test]
+    fn ends_game() {
+        assert_eq!(
+            ending("4k3/8/8/8/8/8/8/3QK3 w - - 100 80"),
+            Some(GameEnding::FiftyMoves)
+        );
+        assert_eq!(ending("4k3/8/8/8/8/8/8/3QK3 w - - 99 80"), None);
+    }
+
+    #[test]
+    fn does_not_override_checkmate() {
+        assert_eq!(
+            ending("k1Q5/8/1K6/8/8/8/8/8 b - - 100 80"),
+            Some(GameEnding::Checkmate)
+        );
+    }
+}
+
+mod insufficient_material {
+    use super::*;
+
+    #[test]
+    fn detects_dead_positions() {
+        for fen in [
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/3NK3 w - - 0 1",
+            "4kb2/8/8/8/8/8/8/4K3 w - - 0 1",
+            "4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1",
+        ] {
+            assert_eq!(ending(fen), Some(GameEnding::InsufficientMaterial), "{fen}");
+        }
+    }
+
+    #[test]
+    fn allows_possible_mates() {
+        for fen in [
+            "4k3/8/8/8/8/8/8/2NNK3 w - - 0 1",
+            "4kb2/8/8/8/8/8/8/3BK3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/2BNK3 w - - 0 1",
+            "4k3/7p/8/8/8/8/8/4K3 w - - 0 1",
+        ] {
+            assert_eq!(ending(fen), None, "{fen}");
+        }
+    }
+
+    #[test]
+    fn is_a_draw_in_search() {
+        let game = Game::try_from_fen("4k3/8/8/8/8/8/8/3NK3 w - - 0 1").unwrap();
+        let result = crate::search::search(game, 2);
+        assert_eq!(result.score, grob_core::Score::Cp(0));
+    }
+}
+
+mod contempt {
+    use grob_core::{Color, Score};
+
+    use super::*;
+
+    #[test]
+    fn is_applied_to_the_side() {
+        let mut game = Game::try_from_fen("4k3/8/8/8/8/8/8/3QK3 w - - 0 1").unwrap();
+        play(&mut game, &["d1d2", "e8e7", "d2d1", "e7e8"]);
+        play(&mut game, &["d1d2", "e8e7", "d2d1", "e7e8"]);
+        for (side, score) in [
+            (Color::White, Score::Cp(-50)),
+            (Color::Black, Score::Cp(50)),
+        ] {
+            let result = crate::search::search_with_contempt(game.clone(), 3, 50, side);
+            assert_eq!(result.score, score);
+        }
+    }
+}
+
+mod search {
+    use grob_core::{
+        spawn_search_server, Color, Game, SearchRequest, SearchResult, ServerCommand,
+        ServerResponse, Window,
+    };
+
+    pub fn search(game: Game, depth: u64) -> SearchResult {
+        search_with_contempt(game, depth, 0, Color::White)
+    }
+
+    pub fn search_with_contempt(
+        game: Game,
+        depth: u64,
+        contempt: i32,
+        side: Color,
+    ) -> SearchResult {
+        let (cmd_send, rsp_recv) = spawn_search_server(1, 1);
+        cmd_send
+            .send(ServerCommand::SetContempt { contempt, side })
+            .unwrap();
+        let request = SearchRequest {
+            game,
+            depth,
+            nodes: None,
+            deadline: None,
+            window: Window::FULL,
+        };
+        cmd_send
+            .send(ServerCommand::ProcessBatch(vec![request]))
+            .unwrap();
+        match rsp_recv.recv().unwrap() {
+            ServerResponse::Search(rsp) => rsp.result,
+            rsp => panic!("unexpected response: {rsp:?}"),
+        }
+    }
+}
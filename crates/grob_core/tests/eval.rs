@@ -0,0 +1,225 @@
+use grob_core::{
+    eval::{Evaluator, TaperedEvaluator, MAX_PHASE},
+    Game, Score,
+};
+
+fn evaluate(fen: &str) -> Score {
+    let game = Game::try_from_fen(fen).expect("Incorrect FEN!");
+    TaperedEvaluator::default().evaluate(&game)
+}
+
+mod incremental {
+    use grob_core::{Game, GameTreeWalker, MoveOrdering};
+
+    /// Walks the perft tree and checks that the incrementally updated
+    /// evaluation terms match the recomputed ones at every node.
+    fn check_tree(fen: &str, depth: u8) {
+        let mut game = Game::try_from_fen(fen).expect("Incorrect FEN!");
+        check_node(&mut game.walk(), depth);
+    }
+
+    fn check_node(node: &mut GameTreeWalker, depth: u8) {
+        let (psq, phase) = (node.game().psq(), node.game().phase());
+        assert_eq!(psq, node.game().compute_psq(), "Incorrect psq!");
+        assert_eq!(phase, node.game().compute_phase(), "Incorrect phase!");
+        if depth == 0 {
+            return;
+        }
+        _ = node.for_each_legal_child_node(MoveOrdering::default(), |node, _| {
+            check_node(node, depth - 1);
+        });
+        assert_eq!(node.game().psq(), psq, "Psq not restored on unmake!");
+        assert_eq!(node.game().phase(), phase, "Phase not restored on unmake!");
+    }
+
+    #[test]
+    fn kiwipete() {
+        check_tree(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            3,
+        );
+    }
+
+    #[test]
+    fn promotions() {
+        check_tree("n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1", 4);
+    }
+}
+
+mod tapered {
+    use super::*;
+
+    #[test]
+    fn initial_position_is_balanced() {
+        let game = Game::initial_position();
+        assert_eq!(game.phase(), MAX_PHASE);
+        // Only the tempo bonus differs, and it goes to the player to move either way.
+        assert_eq!(
+            evaluate("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+            evaluate("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1"),
+        );
+    }
+
+    #[test]
+    fn is_symmetric() {
+        for (fen, mirrored) in [
+            (
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                "r3k2r/pppbbppp/2n2q1P/1P2p3/3pn3/BN2PNP1/P1PPQPB1/R3K2R b KQkq - 0 1",
+            ),
+            (
+                "8/2k5/8/3p4/8/5N2/1K6/8 w - - 0 1",
+                "8/1k6/5n2/8/3P4/8/2K5/8 b - - 0 1",
+            ),
+        ] {
+            assert_eq!(evaluate(fen), evaluate(mirrored), "{fen}");
+        }
+    }
+
+    #[test]
+    fn prefers_material() {
+        let up = evaluate("4k3/8/8/8/8/8/3P4/3RK3 w - - 0 1");
+        let down = evaluate("3rk3/3p4/8/8/8/8/8/4K3 w - - 0 1");
+        assert!(up > Score::Cp(400), "{up:?}");
+        assert!(down < Score::Cp(-400), "{down:?}");
+    }
+
+    #[test]
+    fn prefers_active_king_in_endgame() {
+        let center = evaluate("4k3/8/8/8/4K3/8/8/8 w - - 0 1");
+        let corner = evaluate("4k3/8/8/8/8/8/8/K7 w - - 0 1");
+        assert!(center > corner, "{center:?} <= {corner:?}");
+    }
+}
+
+mod pawns {
+    use super::*;
+
+    #[test]
+    fn prefers_healthy_structure() {
+        let healthy = evaluate("4k3/8/8/8/8/8/3PP3/4K3 w - - 0 1");
+        let doubled = evaluate("4k3/8/8/8/8/4P3/4P3/4K3 w - - 0 1");
+        assert!(healthy > doubled, "{healthy:?} <= {doubled:?}");
+    }
+
+    #[test]
+    fn pawn_cache_is_consistent() {
+        let mut evaluator = TaperedEvaluator::default();
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let mut game = Game::try_from_fen(fen).unwrap();
+        let expected = evaluator.evaluate(&game);
+        _ = game
+            .walk()
+            .for_each_legal_child_node(grob_core::MoveOrdering::default(), |node, _| {
+                evaluator.evaluate(node.game());
+            });
+        assert_eq!(evaluator.evaluate(&game), expected);
+    }
+}
+
+mod trace {
+    use grob_core::{eval::Term, Color, GameTreeWalker, MoveOrdering};
+
+    use super::*;
+
+    fn check_node(evaluator: &mut TaperedEvaluator, node: &mut GameTreeWalker, depth: u8) {
+        let trace = evaluator.trace(node.game());
+        assert_eq!(trace.score(), evaluator.evaluate(node.game()));
+        assert_eq!(trace.phase(), node.game().phase());
+        assert_eq!(
+            trace.term(Term::Material) + trace.term(Term::Pst),
+            node.game().psq()
+        );
+        if depth == 0 {
+            return;
+        }
+        _ = node.for_each_legal_child_node(MoveOrdering::default(), |node, _| {
+            check_node(evaluator, node, depth - 1);
+        });
+    }
+
+    #[test]
+    fn matches_evaluation() {
+        let mut evaluator = TaperedEvaluator::default();
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+        ] {
+            let mut game = Game::try_from_fen(fen).unwrap();
+            check_node(&mut evaluator, &mut game.walk(), 2);
+        }
+    }
+
+    #[test]
+    fn splits_terms_by_color() {
+        let game = Game::try_from_fen("4k3/8/8/8/8/8/3P4/3RK3 b - - 0 1").unwrap();
+        let trace = TaperedEvaluator::default().trace(&game);
+        assert!(trace.get(Term::Material, Color::White).mg > 500);
+        assert_eq!(trace.get(Term::Material, Color::Black).mg, 0);
+        assert_eq!(trace.get(Term::Tempo, Color::White).mg, 0);
+        assert!(trace.get(Term::Tempo, Color::Black).mg > 0);
+    }
+
+    #[test]
+    fn displays_all_terms() {
+        let trace = TaperedEvaluator::default().trace(&Game::initial_position());
+        let table = trace.to_string();
+        for term in [
+            "Material",
+            "PST",
+            "Pawns",
+            "King safety",
+            "Mobility",
+            "Tempo",
+        ] {
+            assert!(table.contains(term), "{term} missing in:\n{table}");
+        }
+    }
+}
+
+mod params {
+    use grob_core::{
+        eval::{evaluate_params, Params, TaperedScore, PARAMS},
+        Color, GameTreeWalker, MoveOrdering,
+    };
+
+    use super::*;
+
+    fn check_node(evaluator: &mut TaperedEvaluator, node: &mut GameTreeWalker, depth: u8) {
+        let game = node.game();
+        let total = evaluate_params(game, &PARAMS);
+        let score = match game.turn() {
+            Color::White => total.taper(game.phase()),
+            Color::Black => -total.taper(game.phase()),
+        };
+        assert_eq!(Score::Cp(score), evaluator.evaluate(game));
+
+        // The evaluation is linear in the parameters.
+        let params: Vec<_> = PARAMS.iter().collect();
+        let mut linear = TaperedScore::ZERO;
+        for (index, coefficient) in evaluate_params(game, &Params::unit()).terms() {
+            linear.mg += (coefficient * params[index].mg as f32) as i32;
+            linear.eg += (coefficient * params[index].eg as f32) as i32;
+        }
+        assert_eq!(linear, total);
+
+        if depth == 0 {
+            return;
+        }
+        _ = node.for_each_legal_child_node(MoveOrdering::default(), |node, _| {
+            check_node(evaluator, node, depth - 1);
+        });
+    }
+
+    #[test]
+    fn matches_evaluation() {
+        let mut evaluator = TaperedEvaluator::default();
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        ] {
+            let mut game = Game::try_from_fen(fen).unwrap();
+            check_node(&mut evaluator, &mut game.walk(), 2);
+        }
+    }
+}
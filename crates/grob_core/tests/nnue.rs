@@ -0,0 +1,192 @@
+#![cfg(feature = "nnue")]
+
+use std::sync::Arc;
+
+use grob_core::{
+    eval::{
+        nnue::{Accumulator, Network, NnueEvaluator, ParseNetworkError, FEATURES},
+        Evaluator, TaperedEvaluator, Term,
+    },
+    Color, Game, GameTreeWalker, MoveOrdering,
+};
+
+const HIDDEN: usize = 32;
+
+/// Serializes a network with pseudo-random weights.
+fn random_network_bytes(hidden: usize) -> Vec<u8> {
+    let mut seed = 0x6A09_E667_F3BC_C908_u64;
+    let mut random = move |range: i16| {
+        seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((seed >> 33) % (2 * range as u64 + 1)) as i16 - range
+    };
+
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(b"GROBNNUE");
+    bytes.extend_from_slice(&1u32.to_le_bytes());
+    bytes.extend_from_slice(&(hidden as u32).to_le_bytes());
+    for _ in 0..FEATURES * hidden {
+        bytes.extend_from_slice(&random(32).to_le_bytes());
+    }
+    for _ in 0..hidden {
+        bytes.extend_from_slice(&random(128).to_le_bytes());
+    }
+    for _ in 0..2 * hidden {
+        bytes.extend_from_slice(&random(64).to_le_bytes());
+    }
+    bytes.extend_from_slice(&1000i32.to_le_bytes());
+    bytes
+}
+
+fn random_network() -> Arc<Network> {
+    Arc::new(Network::from_bytes(&random_network_bytes(HIDDEN)).unwrap())
+}
+
+fn game_with_network(fen: &str, network: &Arc<Network>) -> Game {
+    let mut game = Game::try_from_fen(fen).expect("Incorrect FEN!");
+    game.set_network(Some(network.clone()));
+    game
+}
+
+mod parsing {
+    use super::*;
+
+    #[test]
+    fn parses_network() {
+        let network = Network::from_bytes(&random_network_bytes(HIDDEN)).unwrap();
+        assert_eq!(network.hidden_size(), HIDDEN);
+    }
+
+    #[test]
+    fn rejects_bad_files() {
+        let bytes = random_network_bytes(16);
+        assert_eq!(
+            Network::from_bytes(b"GROB").unwrap_err(),
+            ParseNetworkError::BadMagic
+        );
+        let mut bad_version = bytes.clone();
+        bad_version[8] = 2;
+        assert_eq!(
+            Network::from_bytes(&bad_version).unwrap_err(),
+            ParseNetworkError::BadVersion
+        );
+        let mut bad_hidden = bytes.clone();
+        bad_hidden[12] = 17;
+        assert_eq!(
+            Network::from_bytes(&bad_hidden).unwrap_err(),
+            ParseNetworkError::BadHiddenSize
+        );
+        assert_eq!(
+            Network::from_bytes(&bytes[..bytes.len() - 1]).unwrap_err(),
+            ParseNetworkError::BadLength
+        );
+    }
+}
+
+mod incremental {
+    use super::*;
+
+    /// Walks the perft tree and checks that the incrementally updated
+    /// accumulator matches the recomputed one at every node.
+    fn check_tree(fen: &str, depth: u8) {
+        let mut game = game_with_network(fen, &random_network());
+        check_node(&mut game.walk(), depth);
+    }
+
+    fn check_node(node: &mut GameTreeWalker, depth: u8) {
+        let accumulator = node.game().nnue().unwrap().clone();
+        let network = accumulator.network().clone();
+        assert_eq!(
+            accumulator,
+            Accumulator::new(network, node.game().board()),
+            "Incorrect accumulator!"
+        );
+        if depth == 0 {
+            return;
+        }
+        _ = node.for_each_legal_child_node(MoveOrdering::default(), |node, _| {
+            check_node(node, depth - 1);
+        });
+        assert_eq!(
+            node.game().nnue(),
+            Some(&accumulator),
+            "Accumulator not restored on unmake!"
+        );
+    }
+
+    #[test]
+    fn kiwipete() {
+        check_tree(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            3,
+        );
+    }
+
+    #[test]
+    fn promotions() {
+        check_tree("n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1", 3);
+    }
+
+    #[test]
+    fn en_passant() {
+        check_tree("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 4);
+    }
+}
+
+mod evaluator {
+    use super::*;
+
+    #[test]
+    fn is_symmetric() {
+        let network = random_network();
+        let mut evaluator = NnueEvaluator::default();
+        for (fen, mirrored) in [
+            (
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                "r3k2r/pppbbppp/2n2q1P/1P2p3/3pn3/BN2PNP1/P1PPQPB1/R3K2R b KQkq - 0 1",
+            ),
+            (
+                "8/2k5/8/3p4/8/5N2/1K6/8 w - - 0 1",
+                "8/1k6/5n2/8/3P4/8/2K5/8 b - - 0 1",
+            ),
+        ] {
+            assert_eq!(
+                evaluator.evaluate(&game_with_network(fen, &network)),
+                evaluator.evaluate(&game_with_network(mirrored, &network)),
+                "{fen}"
+            );
+        }
+    }
+
+    #[test]
+    fn trace_matches_evaluation() {
+        let network = random_network();
+        let mut evaluator = NnueEvaluator::default();
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/1k6/5n2/8/3P4/8/2K5/8 b - - 0 1",
+        ] {
+            let game = game_with_network(fen, &network);
+            let trace = evaluator.trace(&game);
+            assert_eq!(trace.score(), evaluator.evaluate(&game), "{fen}");
+            assert_ne!(trace.get(Term::Network, game.turn()).mg, 0, "{fen}");
+        }
+    }
+
+    #[test]
+    fn falls_back_without_network() {
+        let game = Game::initial_position();
+        assert!(game.nnue().is_none());
+        assert_eq!(
+            NnueEvaluator::default().evaluate(&game),
+            TaperedEvaluator::default().evaluate(&game)
+        );
+        assert_eq!(
+            NnueEvaluator::default()
+                .trace(&game)
+                .get(Term::Network, Color::White),
+            Default::default()
+        );
+    }
+}
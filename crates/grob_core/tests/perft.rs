@@ -426,3 +426,62 @@ mod cpw5 {
         perft(FEN, 5, 89_941_194);
     }
 }
+
+mod staged {
+    use grob_core::{ChessMove, Game, LanMove, MoveOrdering};
+
+    const FEN: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+    fn lan(s: &str) -> LanMove {
+        s.parse().unwrap()
+    }
+
+    fn visit(game: &mut Game, policy: MoveOrdering) -> Vec<ChessMove> {
+        let mut moves = Vec::new();
+        _ = game
+            .walk()
+            .for_each_legal_child_node(policy, |_, chess_move| moves.push(chess_move));
+        moves
+    }
+
+    #[test]
+    fn visits_hash_move_and_killers_first() {
+        let mut game = Game::try_from_fen(FEN).unwrap();
+        let hash_move = game.lan_move(lan("a2a3"));
+        let killer = game.lan_move(lan("e1g1"));
+        let capture = game.lan_move(lan("e5f7"));
+        let policy = MoveOrdering::Staged {
+            hash_move,
+            killers: [killer, capture],
+        };
+
+        let staged = visit(&mut game, policy);
+        assert_eq!(staged.first().copied(), hash_move);
+        let quiets_start = staged
+            .iter()
+            .skip(1)
+            .position(|m| !m.hint().is_capture() && !m.hint().is_promotion())
+            .unwrap();
+        assert_eq!(staged.get(quiets_start + 1).copied(), killer);
+
+        let mut staged = staged;
+        let mut expected = visit(&mut game, MoveOrdering::default());
+        staged.sort_by_key(|m| m.lan().to_string());
+        expected.sort_by_key(|m| m.lan().to_string());
+        assert_eq!(staged, expected);
+    }
+
+    #[test]
+    fn skips_later_stages_on_exhaust() {
+        let mut game = Game::try_from_fen(FEN).unwrap();
+        let mut visited = 0;
+        _ = game
+            .walk()
+            .for_each_legal_child_node(MoveOrdering::MvvLva, |node, chess_move| {
+                visited += 1;
+                assert!(chess_move.hint().is_capture());
+                node.exhaust_moves();
+            });
+        assert_eq!(visited, 1);
+    }
+}
@@ -0,0 +1,147 @@
+mod collect {
+    use grob_core::Game;
+
+    /// Plays the moves from the initial position and returns the Polyglot key.
+    pub fn key_after(moves: &[&str]) -> u64 {
+        let mut game = Game::initial_position();
+        for lan in moves {
+            let chess_move = game
+                .lan_move(lan.parse().expect("Incorrect LAN!"))
+                .expect("Illegal move!");
+            assert!(game.try_make_move(chess_move), "Illegal move!");
+        }
+        game.polyglot_key()
+    }
+
+    /// Encodes a book entry in the Polyglot format.
+    pub fn entry(key: u64, packed_move: u16, weight: u16) -> [u8; 16] {
+        let mut bytes = [0; 16];
+        bytes[0..8].copy_from_slice(&key.to_be_bytes());
+        bytes[8..10].copy_from_slice(&packed_move.to_be_bytes());
+        bytes[10..12].copy_from_slice(&weight.to_be_bytes());
+        bytes
+    }
+}
+
+pub use collect::{entry, key_after};
+
+mod keys {
+    use crate::key_after;
+
+    // Reference keys from the Polyglot book format specification.
+
+    #[test]
+    fn initial() {
+        assert_eq!(key_after(&[]), 0x463b96181691fc9c);
+    }
+
+    #[test]
+    fn double_pushes() {
+        assert_eq!(key_after(&["e2e4"]), 0x823c9b50fd114196);
+        assert_eq!(key_after(&["e2e4", "d7d5"]), 0x0756b94461c50fb0);
+        assert_eq!(key_after(&["e2e4", "d7d5", "e4e5"]), 0x662fafb965db29d4);
+    }
+
+    #[test]
+    fn en_passant() {
+        assert_eq!(
+            key_after(&["e2e4", "d7d5", "e4e5", "f7f5"]),
+            0x22a48b5a8e47ff78
+        );
+        assert_eq!(
+            key_after(&["a2a4", "b7b5", "h2h4", "b5b4", "c2c4"]),
+            0x3c8123ea7b067637
+        );
+    }
+
+    #[test]
+    fn castling_rights() {
+        assert_eq!(
+            key_after(&["e2e4", "d7d5", "e4e5", "f7f5", "e1e2"]),
+            0x652a607ca3f242c1
+        );
+        assert_eq!(
+            key_after(&["e2e4", "d7d5", "e4e5", "f7f5", "e1e2", "e8f7"]),
+            0x00fdd303c946bdd9
+        );
+        assert_eq!(
+            key_after(&["a2a4", "b7b5", "h2h4", "b5b4", "c2c4", "b4c3", "a1a3"]),
+            0x5c3f9b829b279560
+        );
+    }
+}
+
+mod book {
+    use grob_core::{
+        book::{Book, ParseBookError},
+        Game, LanMove,
+    };
+
+    use crate::entry;
+
+    /// e2e4 in the Polyglot move encoding.
+    const E2E4: u16 = (0o14 << 6) | 0o34;
+    /// d2d4 in the Polyglot move encoding.
+    const D2D4: u16 = (0o13 << 6) | 0o33;
+    /// White king takes its own rook on h1, i.e. short castling.
+    const E1H1: u16 = (0o04 << 6) | 0o07;
+
+    fn lan(s: &str) -> Option<LanMove> {
+        s.parse().ok()
+    }
+
+    fn book(entries: &[[u8; 16]]) -> Book {
+        Book::from_bytes(&entries.concat()).unwrap()
+    }
+
+    #[test]
+    fn rejects_truncated_file() {
+        let bytes = entry(0, E2E4, 1);
+        assert_eq!(
+            Book::from_bytes(&bytes[..15]).unwrap_err(),
+            ParseBookError::BadLength
+        );
+    }
+
+    #[test]
+    fn finds_moves_by_key() {
+        let game = Game::initial_position();
+        let key = game.polyglot_key();
+        let book = book(&[
+            entry(key + 1, D2D4, 1),
+            entry(key, E2E4, 3),
+            entry(key, D2D4, 0),
+            entry(key - 1, D2D4, 1),
+        ]);
+        assert_eq!(book.len(), 4);
+        assert_eq!(book.entries(key).len(), 2);
+
+        let moves = book.moves(&game);
+        assert_eq!(moves.len(), 1);
+        assert_eq!(Some(moves[0].0.lan()), lan("e2e4"));
+        assert_eq!(moves[0].1, 3);
+    }
+
+    #[test]
+    fn picks_weighted_moves() {
+        let game = Game::initial_position();
+        let key = game.polyglot_key();
+        let book = book(&[entry(key, E2E4, 1), entry(key, D2D4, 3)]);
+        let pick = |random| book.pick_move(&game, random).map(|m| m.lan());
+        assert_eq!(pick(0), lan("e2e4"));
+        assert_eq!(pick(1), lan("d2d4"));
+        assert_eq!(pick(3), lan("d2d4"));
+        assert_eq!(pick(4), lan("e2e4"));
+
+        let out_of_book = Game::try_from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(book.pick_move(&out_of_book, 0), None);
+    }
+
+    #[test]
+    fn decodes_castling() {
+        let game = Game::try_from_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+        let book = book(&[entry(game.polyglot_key(), E1H1, 1)]);
+        let chess_move = book.pick_move(&game, 0).unwrap();
+        assert_eq!(Some(chess_move.lan()), lan("e1g1"));
+    }
+}
@@ -0,0 +1,219 @@
+mod collect {
+    use grob_core::{
+        spawn_search_server, Game, SearchRequest, SearchResult, ServerCommand, ServerResponse,
+        Window,
+    };
+
+    pub fn search(fen: &str, depth: u64) -> SearchResult {
+        search_window(fen, depth, Window::FULL)
+    }
+
+    pub fn search_window(fen: &str, depth: u64, window: Window) -> SearchResult {
+        let (cmd_send, rsp_recv) = spawn_search_server(1, 1);
+        let game = Game::try_from_fen(fen).expect("Incorrect FEN!");
+        let request = SearchRequest {
+            game,
+            depth,
+            nodes: None,
+            deadline: None,
+            window,
+        };
+        cmd_send
+            .send(ServerCommand::ProcessBatch(vec![request]))
+            .unwrap();
+        match rsp_recv.recv().unwrap() {
+            ServerResponse::Search(rsp) => rsp.result,
+            rsp => panic!("unexpected response: {rsp:?}"),
+        }
+    }
+}
+
+pub use collect::{search, search_window};
+
+mod quiescence {
+    use grob_core::{
+        eval::{Evaluator, TaperedEvaluator},
+        Game, LanMove, Score,
+    };
+
+    use crate::search;
+
+    /// Returns the static evaluation after the moves are played
+    /// for the player who was to move before them.
+    fn static_eval(fen: &str, moves: &[&str]) -> Score {
+        let mut game = Game::try_from_fen(fen).expect("Incorrect FEN!");
+        for lan_move in moves {
+            let chess_move = game.lan_move(lan_move.parse().unwrap()).unwrap();
+            assert!(game.try_make_move(chess_move));
+        }
+        let score = TaperedEvaluator::default().evaluate(&game);
+        match moves.len() % 2 {
+            0 => score,
+            _ => score.prev(),
+        }
+    }
+
+    #[test]
+    fn captures_hanging_queen() {
+        let fen = "4k3/8/8/3q4/4P3/8/8/4K3 w - - 0 1";
+        let result = search(fen, 0);
+        assert_eq!(result.score, static_eval(fen, &["e4d5"]));
+        assert_eq!(
+            result.best_move.map(|m| m.lan()),
+            "e4d5".parse::<LanMove>().ok()
+        );
+    }
+
+    #[test]
+    fn does_not_stand_pat_in_check() {
+        let result = search("4k3/8/8/8/8/8/5PPP/3r2K1 w - - 0 1", 0);
+        assert_eq!(result.score, Score::Mated(0));
+    }
+
+    #[test]
+    fn sees_defended_piece() {
+        // Taking either pawn loses the queen, so standing pat is the best.
+        let fen = "4k3/8/2p5/3p4/8/8/3Q4/4K3 w - - 0 1";
+        let result = search(fen, 0);
+        assert_eq!(result.score, static_eval(fen, &[]));
+        assert_eq!(result.best_move, None);
+    }
+
+    #[test]
+    fn promotes_when_far_behind() {
+        // The check loses the queen, which is only taken back
+        // by a promotion that also mates.
+        let result = search("1n2k3/1P1ppp2/8/8/2Q5/8/2q5/4K3 w - - 0 1", 1);
+        assert_eq!(result.score, Score::Mating(2));
+    }
+}
+
+mod mate {
+    use grob_core::Score;
+
+    use crate::search;
+
+    #[test]
+    fn finds_mate_in_two() {
+        let result = search("k7/8/2K5/8/8/8/8/1R6 w - - 0 1", 3);
+        assert_eq!(result.score, Score::Mating(2));
+    }
+
+    #[test]
+    fn keeps_mate_distance_deeper() {
+        // Deeper searches try the null moves, which must not shorten the mate.
+        let result = search("k7/8/2K5/8/8/8/8/1R6 w - - 0 1", 7);
+        assert_eq!(result.score, Score::Mating(2));
+    }
+}
+
+mod pv {
+    use grob_core::LanMove;
+
+    use crate::search;
+
+    #[test]
+    fn leads_to_mate() {
+        let result = search("k7/8/2K5/8/8/8/8/1R6 w - - 0 1", 3);
+        let pv: Vec<LanMove> = result.pv.iter().map(|m| m.lan()).collect();
+        let expected: Vec<LanMove> = ["c6c7", "a8a7", "b1a1"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        assert_eq!(pv, expected);
+        assert_eq!(result.pv.first(), result.best_move);
+    }
+
+    #[test]
+    fn is_empty_without_moves() {
+        let result = search("k7/1Q6/1K6/8/8/8/8/8 b - - 0 1", 2);
+        assert!(result.pv.is_empty());
+        assert_eq!(result.best_move, None);
+    }
+}
+
+mod window {
+    use grob_core::{Score, Window};
+
+    use crate::{search, search_window};
+
+    const FEN: &str = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3";
+
+    #[test]
+    fn bounds_the_score() {
+        let exact = search(FEN, 3).score;
+        let below = Window::null(exact.add_cp(-1));
+        assert!(search_window(FEN, 3, below).score >= below.beta);
+        let above = Window::null(exact);
+        assert!(search_window(FEN, 3, above).score <= above.alpha);
+    }
+
+    #[test]
+    fn keeps_mate_in_one_exact() {
+        let result = search("k7/2K5/8/8/8/8/8/1R6 w - - 0 1", 2);
+        assert_eq!(result.score, Score::Mating(1));
+        assert!(Window::FULL.is_exact(result.score));
+    }
+
+    #[test]
+    fn orders_successors() {
+        for score in [
+            Score::Mated(0),
+            Score::Mated(u64::MAX),
+            Score::Cp(-1),
+            Score::Cp(i32::MAX),
+            Score::Mating(2),
+        ] {
+            assert!(score.successor() > score, "{score:?}");
+        }
+        assert_eq!(Score::Mating(0).successor(), Score::Mating(0));
+    }
+}
+
+mod hash_stats {
+    use grob_core::{
+        spawn_search_server, Game, SearchRequest, ServerCommand, ServerResponse, Window,
+    };
+
+    fn get_hash_stats(
+        cmd_send: &crossbeam::channel::Sender<ServerCommand>,
+        rsp_recv: &crossbeam::channel::Receiver<ServerResponse>,
+    ) -> grob_core::HashStats {
+        cmd_send.send(ServerCommand::GetHashStats).unwrap();
+        match rsp_recv.recv().unwrap() {
+            ServerResponse::HashStats(stats) => stats,
+            rsp => panic!("unexpected response: {rsp:?}"),
+        }
+    }
+
+    #[test]
+    fn counts_probes() {
+        let (cmd_send, rsp_recv) = spawn_search_server(1, 1);
+        let stats = get_hash_stats(&cmd_send, &rsp_recv);
+        assert_eq!(stats.capacity, 1024 * 1024 / 16);
+        assert_eq!(stats.hashfull, 0);
+        assert_eq!(stats.hits + stats.misses, 0);
+
+        let request = SearchRequest {
+            game: Game::initial_position(),
+            depth: 3,
+            nodes: None,
+            deadline: None,
+            window: Window::FULL,
+        };
+        cmd_send
+            .send(ServerCommand::ProcessBatch(vec![request.clone(), request]))
+            .unwrap();
+        for _ in 0..2 {
+            assert!(matches!(rsp_recv.recv(), Ok(ServerResponse::Search(_))));
+        }
+        let stats = get_hash_stats(&cmd_send, &rsp_recv);
+        assert!(stats.misses > 0);
+        assert!(stats.hits > 0);
+
+        cmd_send.send(ServerCommand::ClearHash).unwrap();
+        let stats = get_hash_stats(&cmd_send, &rsp_recv);
+        assert_eq!(stats.hashfull, 0);
+        assert_eq!(stats.hits + stats.misses + stats.overwrites, 0);
+    }
+}
@@ -0,0 +1,182 @@
+mod collect {
+    use grob_core::{Game, GameTreeWalker, MoveOrdering};
+
+    /// Walks the perft tree and checks that the incrementally updated hash
+    /// matches the recomputed one at every node, including after unmaking.
+    pub fn check_tree(fen: &str, depth: u8) {
+        let mut game = Game::try_from_fen(fen).expect("Incorrect FEN!");
+        check_node(&mut game.walk(), depth);
+    }
+
+    fn check_node(node: &mut GameTreeWalker, depth: u8) {
+        let hash = node.game().zobrist();
+        assert_eq!(hash, node.game().compute_zobrist(), "Incorrect hash!");
+        assert_eq!(
+            node.game().pawn_zobrist(),
+            node.game().compute_pawn_zobrist(),
+            "Incorrect pawn hash!"
+        );
+        if depth == 0 {
+            return;
+        }
+        _ = node.for_each_legal_child_node(MoveOrdering::default(), |node, chess_move| {
+            assert_ne!(
+                node.game().zobrist(),
+                hash,
+                "Hash unchanged by {chess_move:?}!"
+            );
+            check_node(node, depth - 1);
+        });
+        assert_eq!(node.game().zobrist(), hash, "Hash not restored on unmake!");
+    }
+}
+
+pub use collect::check_tree;
+
+mod incremental {
+    use crate::check_tree;
+
+    #[test]
+    fn initial() {
+        check_tree(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            4,
+        );
+    }
+
+    #[test]
+    fn kiwipete() {
+        check_tree(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            3,
+        );
+    }
+
+    #[test]
+    fn mirrored() {
+        check_tree(
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            3,
+        );
+        check_tree(
+            "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
+            3,
+        );
+    }
+
+    #[test]
+    fn cpw3() {
+        check_tree("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 4);
+    }
+
+    #[test]
+    fn cpw5() {
+        check_tree(
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            3,
+        );
+    }
+}
+
+mod null_move {
+    use grob_core::{File, Game};
+
+    #[test]
+    fn passes_the_turn() {
+        let mut game = Game::initial_position();
+        let chess_move = game.lan_move("e2e4".parse().unwrap()).unwrap();
+        game.make_move(chess_move);
+        let hash = game.zobrist();
+        assert_eq!(game.en_passant(), Some(File::E));
+
+        game.make_null_move();
+        assert!(game.was_null_move());
+        assert_eq!(game.en_passant(), None);
+        assert_ne!(game.zobrist(), hash);
+        assert_eq!(game.zobrist(), game.compute_zobrist());
+        let passed = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 1";
+        assert_eq!(
+            game.zobrist(),
+            Game::try_from_fen(passed).unwrap().zobrist()
+        );
+
+        game.unmake_null_move();
+        assert!(!game.was_null_move());
+        assert_eq!(game.en_passant(), Some(File::E));
+        assert_eq!(game.zobrist(), hash);
+        assert_eq!(game.halfmove_clock(), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn is_not_unmade_as_a_move() {
+        let mut game = Game::initial_position();
+        let chess_move = game.lan_move("e2e4".parse().unwrap()).unwrap();
+        game.make_move(chess_move);
+        game.unmake_null_move();
+    }
+}
+
+mod distinct {
+    use grob_core::Game;
+
+    fn hash(fen: &str) -> u64 {
+        Game::try_from_fen(fen)
+            .expect("Incorrect FEN!")
+            .zobrist()
+            .get()
+    }
+
+    #[test]
+    fn piece_colors() {
+        assert_ne!(
+            hash("4k3/8/8/8/4P3/8/8/4K3 w - - 0 1"),
+            hash("4k3/8/8/8/4p3/8/8/4K3 w - - 0 1")
+        );
+    }
+
+    #[test]
+    fn piece_kinds() {
+        assert_ne!(
+            hash("4k3/8/8/8/4N3/8/8/4K3 w - - 0 1"),
+            hash("4k3/8/8/8/4B3/8/8/4K3 w - - 0 1")
+        );
+    }
+
+    #[test]
+    fn squares() {
+        assert_ne!(
+            hash("4k3/8/8/8/4P3/8/8/4K3 w - - 0 1"),
+            hash("4k3/8/8/8/3P4/8/8/4K3 w - - 0 1")
+        );
+    }
+}
+
+mod golden {
+    use grob_core::Game;
+
+    // Hashes must not change between builds. If the Zobrist keys are
+    // changed on purpose, these values have to be updated as well.
+
+    #[test]
+    fn initial() {
+        assert_eq!(Game::initial_position().zobrist().get(), 0xbcacee5f1ba02afd);
+    }
+
+    #[test]
+    fn kiwipete() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let game = Game::try_from_fen(fen).unwrap();
+        assert_eq!(game.zobrist().get(), 0x4282f60d3d6dbd12);
+    }
+
+    #[test]
+    fn after_moves() {
+        let mut game = Game::initial_position();
+        for lan in ["e2e4", "d7d5", "e4e5", "f7f5"] {
+            let chess_move = game.lan_move(lan.parse().unwrap()).unwrap();
+            assert!(game.try_make_move(chess_move));
+        }
+        assert_eq!(game.zobrist().get(), 0xb431f2567aeb252b);
+    }
+}
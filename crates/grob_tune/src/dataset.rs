@@ -0,0 +1,100 @@
+//! Positions labeled with the game results.
+
+use std::{error::Error, fmt};
+
+use grob_core::{Game, ParseFenError};
+
+/// A position labeled with the result of the game it was played in.
+#[derive(Debug, Clone)]
+pub struct LabeledPosition {
+    /// The position.
+    pub game: Game,
+    /// Result of the game for the white player:
+    /// 1 for a win, 0.5 for a draw and 0 for a loss.
+    pub result: f32,
+}
+
+/// An error that originated from parsing an EPD line.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ParseEpdError {
+    /// The position is not valid.
+    BadPosition(ParseFenError),
+    /// The line has no `c9` opcode.
+    MissingResult,
+    /// The `c9` opcode is not a game result.
+    BadResult,
+}
+
+impl fmt::Display for ParseEpdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl Error for ParseEpdError {}
+
+impl LabeledPosition {
+    /// Parses an [EPD] line with the game result stored in the `c9` opcode,
+    /// for example `8/8/8/8/8/8/k7/2K1Q3 w - - c9 "1-0";`.
+    ///
+    /// The halfmove and fullmove clocks are not used by the evaluation,
+    /// so they are ignored.
+    ///
+    /// [EPD]: https://www.chessprogramming.org/Extended_Position_Description
+    pub fn from_epd(line: &str) -> Result<Self, ParseEpdError> {
+        let mut words = line.split_whitespace();
+        let position: Vec<_> = words.by_ref().take(4).collect();
+        let fen = format!("{} 0 1", position.join(" "));
+        let game = Game::try_from_fen(&fen).map_err(ParseEpdError::BadPosition)?;
+
+        let operations = words.collect::<Vec<_>>().join(" ");
+        let result = operations
+            .split(';')
+            .find_map(|operation| operation.trim().strip_prefix("c9 "))
+            .ok_or(ParseEpdError::MissingResult)?;
+        let result = match result.trim().trim_matches('"') {
+            "1-0" => 1.0,
+            "1/2-1/2" => 0.5,
+            "0-1" => 0.0,
+            _ => return Err(ParseEpdError::BadResult),
+        };
+        Ok(Self { game, result })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let position =
+            LabeledPosition::from_epd("8/8/8/8/8/8/k7/2K1Q3 b - - hmvc 3; c9 \"1-0\";").unwrap();
+        assert_eq!(position.result, 1.0);
+        assert_eq!(
+            position.game.zobrist(),
+            Game::try_from_fen("8/8/8/8/8/8/k7/2K1Q3 b - - 0 1")
+                .unwrap()
+                .zobrist()
+        );
+
+        let draw = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - c9 \"1/2-1/2\";";
+        assert_eq!(LabeledPosition::from_epd(draw).unwrap().result, 0.5);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            LabeledPosition::from_epd("8/8/8/8/8/8/k7/2K1Q3 b - -").unwrap_err(),
+            ParseEpdError::MissingResult
+        );
+        assert_eq!(
+            LabeledPosition::from_epd("8/8/8/8/8/8/k7/2K1Q3 b - - c9 \"*\";").unwrap_err(),
+            ParseEpdError::BadResult
+        );
+        assert!(matches!(
+            LabeledPosition::from_epd("8/8/8 b - - c9 \"1-0\";"),
+            Err(ParseEpdError::BadPosition(_))
+        ));
+    }
+}
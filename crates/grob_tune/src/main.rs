@@ -0,0 +1,119 @@
+//! Tuner
+//!
+//! This crate tunes the evaluation parameters with the [Texel's tuning method].
+//!
+//! Usage: `grob_tune <path to an .epd file> [--epochs <count>]
+//! [--learning-rate <rate>] [--threads <count>] [--output <path to a .rs file>]`
+//!
+//! [Texel's tuning method]: https://www.chessprogramming.org/Texel%27s_Tuning_Method
+
+use std::{env, fs, process};
+
+use grob_core::eval::PARAMS;
+
+use crate::{dataset::LabeledPosition, tuner::Tuner};
+
+mod dataset;
+mod resolve;
+mod source;
+mod tuner;
+
+/// Number of the epochs between the progress reports.
+const REPORT_INTERVAL: usize = 10;
+
+/// Settings of the tuning.
+#[derive(Debug, Clone)]
+struct Settings {
+    /// Path to the EPD file with the positions.
+    positions: String,
+    /// Number of the gradient descent steps over all positions.
+    epochs: usize,
+    /// Learning rate of the optimizer.
+    learning_rate: f64,
+    /// Number of threads of the quiescence search.
+    threads: usize,
+    /// Path to write the tuned parameters to, they are printed if `None`.
+    output: Option<String>,
+}
+
+fn main() {
+    let settings = parse_args().unwrap_or_else(|e| {
+        eprintln!("{e}");
+        process::exit(1);
+    });
+
+    let mut positions = load_positions(&settings.positions).unwrap_or_else(|e| {
+        eprintln!(
+            "Failed to load the positions from {}: {e}",
+            settings.positions
+        );
+        process::exit(1);
+    });
+    eprintln!("Loaded {} positions", positions.len());
+    resolve::resolve(&mut positions, settings.threads);
+    eprintln!("Resolved the quiescence search lines");
+
+    let mut tuner = Tuner::new(&positions, &PARAMS, settings.learning_rate);
+    drop(positions);
+    let scale = tuner.fit_scale();
+    eprintln!("K = {scale:.4}, error = {:.6}", tuner.error());
+    for epoch in 1..=settings.epochs {
+        tuner.step();
+        if epoch % REPORT_INTERVAL == 0 || epoch == settings.epochs {
+            eprintln!("Epoch {epoch}, error = {:.6}", tuner.error());
+        }
+    }
+
+    let source = source::render(&tuner.params());
+    match settings.output {
+        Some(path) => fs::write(&path, source).unwrap_or_else(|e| {
+            eprintln!("Failed to write the parameters to {path}: {e}");
+            process::exit(1);
+        }),
+        None => print!("{source}"),
+    }
+}
+
+fn parse_args() -> Result<Settings, String> {
+    let mut settings = Settings {
+        positions: String::new(),
+        epochs: 1000,
+        learning_rate: 1.0,
+        threads: 1,
+        output: None,
+    };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("Missing value of {arg}"));
+        match arg.as_str() {
+            "--epochs" => settings.epochs = value()?.parse().map_err(|e| format!("{e}"))?,
+            "--learning-rate" => {
+                settings.learning_rate = value()?.parse().map_err(|e| format!("{e}"))?
+            }
+            "--threads" => settings.threads = value()?.parse().map_err(|e| format!("{e}"))?,
+            "--output" => settings.output = Some(value()?),
+            _ if settings.positions.is_empty() => settings.positions = arg,
+            _ => return Err(format!("Unknown argument: {arg}")),
+        }
+    }
+    if settings.positions.is_empty() {
+        return Err(String::from("Missing the path to the positions"));
+    }
+    Ok(settings)
+}
+
+/// Loads the positions, the lines that can not be parsed are skipped.
+fn load_positions(path: &str) -> Result<Vec<LabeledPosition>, String> {
+    let epd = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let mut positions = Vec::new();
+    for (number, line) in epd.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match LabeledPosition::from_epd(line) {
+            Ok(position) => positions.push(position),
+            Err(e) => eprintln!("Skipping line {}: {e}", number + 1),
+        }
+    }
+    Ok(positions)
+}
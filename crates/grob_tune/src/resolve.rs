@@ -0,0 +1,47 @@
+//! Quiescence resolution of the positions.
+//!
+//! The static evaluation is only meaningful in the quiet positions,
+//! so every position is replaced by the one at the end of the principal
+//! variation of its [quiescence search].
+//!
+//! [quiescence search]: https://www.chessprogramming.org/Quiescence_Search
+
+use grob_core::{SearchRequest, ServerCommand, ServerResponse, Window, spawn_search_server};
+
+use crate::dataset::LabeledPosition;
+
+/// Number of positions sent to the search server at once.
+const BATCH_SIZE: usize = 4096;
+/// Transposition table size in mebibytes (MiB).
+const HASH_MIB: usize = 16;
+
+/// Replaces the positions with the quiet positions of their quiescence search lines.
+pub fn resolve(positions: &mut [LabeledPosition], threads: usize) {
+    let (cmd_send, rsp_recv) = spawn_search_server(threads, HASH_MIB);
+    for batch in positions.chunks_mut(BATCH_SIZE) {
+        let requests = batch
+            .iter()
+            .map(|position| SearchRequest {
+                game: position.game.clone(),
+                depth: 0,
+                nodes: None,
+                deadline: None,
+                window: Window::FULL,
+            })
+            .collect::<Vec<_>>();
+        cmd_send
+            .send(ServerCommand::ProcessBatch(requests))
+            .unwrap();
+
+        for _ in 0..batch.len() {
+            let rsp = match rsp_recv.recv().unwrap() {
+                ServerResponse::Search(rsp) => rsp,
+                rsp => panic!("unexpected response: {rsp:?}"),
+            };
+            let game = &mut batch[rsp.batch_index].game;
+            for chess_move in rsp.result.pv.iter() {
+                assert!(game.try_make_move(chess_move), "illegal move in the PV");
+            }
+        }
+    }
+}
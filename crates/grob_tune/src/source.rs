@@ -0,0 +1,95 @@
+//! Rust source of the tuned parameters.
+
+use std::fmt::{self, Write};
+
+use grob_core::eval::{Params, TaperedScore};
+
+/// Number of scores written on a single line.
+const SCORES_PER_LINE: usize = 8;
+
+/// Returns the contents of `crates/grob_core/src/eval/params/values.rs`
+/// that defines the parameters.
+pub fn render(params: &Params<TaperedScore>) -> String {
+    let mut source = String::new();
+    write_source(&mut source, params).unwrap();
+    source
+}
+
+fn write_source(f: &mut String, params: &Params<TaperedScore>) -> fmt::Result {
+    writeln!(f, "//! Evaluation parameters")?;
+    writeln!(f, "//!")?;
+    writeln!(
+        f,
+        "//! Generated by `grob_tune`, see [`Params`] for the meaning of the fields."
+    )?;
+    writeln!(f)?;
+    writeln!(f, "use crate::eval::{{Params, TaperedScore}};")?;
+    writeln!(f)?;
+    writeln!(f, "const fn s(mg: i32, eg: i32) -> TaperedScore {{")?;
+    writeln!(f, "    TaperedScore::new(mg, eg)")?;
+    writeln!(f, "}}")?;
+    writeln!(f)?;
+    writeln!(
+        f,
+        "/// Parameters used by the [`TaperedEvaluator`](crate::eval::TaperedEvaluator)."
+    )?;
+    writeln!(f, "#[rustfmt::skip]")?;
+    writeln!(f, "pub const PARAMS: Params<TaperedScore> = Params {{")?;
+    write_array(f, 1, "material", &params.material)?;
+    writeln!(f, "    pst: [")?;
+    for table in &params.pst {
+        write_array(f, 2, "", table)?;
+    }
+    writeln!(f, "    ],")?;
+    write_score(f, "doubled", params.doubled)?;
+    write_score(f, "isolated", params.isolated)?;
+    write_score(f, "backward", params.backward)?;
+    write_array(f, 1, "connected", &params.connected)?;
+    write_array(f, 1, "passed", &params.passed)?;
+    write_array(f, 1, "passed_free_path", &params.passed_free_path)?;
+    write_array(f, 1, "mobility", &params.mobility)?;
+    write_array(f, 1, "safety", &params.safety)?;
+    write_score(f, "shield_advanced", params.shield_advanced)?;
+    write_score(f, "shield_missing", params.shield_missing)?;
+    write_score(f, "semi_open_file", params.semi_open_file)?;
+    write_score(f, "open_file", params.open_file)?;
+    write_score(f, "tempo", params.tempo)?;
+    writeln!(f, "}};")
+}
+
+/// Writes a field with a single score.
+fn write_score(f: &mut String, name: &str, score: TaperedScore) -> fmt::Result {
+    writeln!(f, "    {name}: {},", score_literal(score))
+}
+
+/// Writes an array of scores, either as a field or as an element
+/// of another array if the name is empty.
+fn write_array(f: &mut String, level: usize, name: &str, scores: &[TaperedScore]) -> fmt::Result {
+    let indent = "    ".repeat(level);
+    match name {
+        "" => writeln!(f, "{indent}[")?,
+        name => writeln!(f, "{indent}{name}: [")?,
+    }
+    for line in scores.chunks(SCORES_PER_LINE) {
+        let line: Vec<_> = line.iter().map(|&score| score_literal(score)).collect();
+        writeln!(f, "{indent}    {},", line.join(", "))?;
+    }
+    writeln!(f, "{indent}],")
+}
+
+fn score_literal(score: TaperedScore) -> String {
+    format!("s({:4}, {:4})", score.mg, score.eg)
+}
+
+#[cfg(test)]
+mod tests {
+    use grob_core::eval::PARAMS;
+
+    use super::*;
+
+    #[test]
+    fn test_renders_current_params() {
+        let current = include_str!("../../grob_core/src/eval/params/values.rs");
+        assert_eq!(render(&PARAMS), current);
+    }
+}
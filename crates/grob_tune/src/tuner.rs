@@ -0,0 +1,233 @@
+//! [Texel tuning]
+//!
+//! The evaluation is mapped to the expected game result with a sigmoid,
+//! and the parameters are optimized to minimize the mean squared error
+//! between the expected and the actual results. Since the evaluation is
+//! linear in the parameters, every position is stored as the [`Coefficients`]
+//! of the parameters, so the evaluation and its gradient are cheap to compute.
+//!
+//! [Texel tuning]: https://www.chessprogramming.org/Texel%27s_Tuning_Method
+
+use grob_core::eval::{Coefficients, MAX_PHASE, Params, TaperedScore, evaluate_params};
+
+use crate::dataset::LabeledPosition;
+
+/// Smallest scaling constant considered by [`Tuner::fit_scale`].
+const MIN_SCALE: f64 = 0.0;
+/// Largest scaling constant considered by [`Tuner::fit_scale`].
+const MAX_SCALE: f64 = 4.0;
+/// Number of the golden-section search iterations of [`Tuner::fit_scale`].
+const SCALE_ITERATIONS: usize = 64;
+
+/// Decay rate of the first moment of the [Adam] optimizer.
+///
+/// [Adam]: https://arxiv.org/abs/1412.6980
+const BETA1: f64 = 0.9;
+/// Decay rate of the second moment of the Adam optimizer.
+const BETA2: f64 = 0.999;
+/// Term of the Adam optimizer that prevents the division by zero.
+const EPSILON: f64 = 1e-8;
+
+/// A position prepared for the tuning.
+#[derive(Debug, Clone)]
+struct Entry {
+    /// Pairs of parameter indices and coefficients from the white player's point of view.
+    coefficients: Box<[(u32, f32)]>,
+    /// Weight of the middlegame score, the endgame score is weighted by `1 - mg_weight`.
+    mg_weight: f32,
+    /// Result of the game for the white player.
+    result: f32,
+}
+
+/// Optimizes the evaluation parameters for a set of positions.
+#[derive(Debug, Clone)]
+pub struct Tuner {
+    entries: Vec<Entry>,
+    /// Middlegame and endgame values of the parameters in the order of [`Params::iter`].
+    params: Vec<[f64; 2]>,
+    /// Scaling constant `K` of the sigmoid.
+    scale: f64,
+    /// Learning rate of the Adam optimizer.
+    learning_rate: f64,
+    /// First moment estimates of the Adam optimizer.
+    momentum: Vec<[f64; 2]>,
+    /// Second moment estimates of the Adam optimizer.
+    velocity: Vec<[f64; 2]>,
+    /// Number of the optimizer steps made so far.
+    steps: i32,
+}
+
+impl Tuner {
+    /// Creates a tuner that starts with the parameters.
+    pub fn new(
+        positions: &[LabeledPosition],
+        params: &Params<TaperedScore>,
+        learning_rate: f64,
+    ) -> Self {
+        let unit = Params::unit();
+        let entries = positions
+            .iter()
+            .map(|position| {
+                let coefficients: Coefficients = evaluate_params(&position.game, &unit);
+                let phase = position.game.phase().clamp(0, MAX_PHASE);
+                Entry {
+                    coefficients: coefficients
+                        .terms()
+                        .map(|(index, coefficient)| (index as u32, coefficient))
+                        .collect(),
+                    mg_weight: phase as f32 / MAX_PHASE as f32,
+                    result: position.result,
+                }
+            })
+            .collect();
+        let params: Vec<_> = params
+            .iter()
+            .map(|score| [score.mg as f64, score.eg as f64])
+            .collect();
+        let zeros = vec![[0.0; 2]; params.len()];
+        Self {
+            entries,
+            params,
+            scale: 1.0,
+            learning_rate,
+            momentum: zeros.clone(),
+            velocity: zeros,
+            steps: 0,
+        }
+    }
+    /// Finds the scaling constant `K` that minimizes the error
+    /// with the current parameters.
+    pub fn fit_scale(&mut self) -> f64 {
+        // Golden-section search, the error is unimodal in `K`.
+        let ratio = (5f64.sqrt() - 1.0) / 2.0;
+        let (mut low, mut high) = (MIN_SCALE, MAX_SCALE);
+        for _ in 0..SCALE_ITERATIONS {
+            let left = high - ratio * (high - low);
+            let right = low + ratio * (high - low);
+            if self.error_with_scale(left) < self.error_with_scale(right) {
+                high = right;
+            } else {
+                low = left;
+            }
+        }
+        self.scale = (low + high) / 2.0;
+        self.scale
+    }
+    /// Returns the mean squared error of the expected game results.
+    pub fn error(&self) -> f64 {
+        self.error_with_scale(self.scale)
+    }
+    /// Makes a single step of the gradient descent over all positions.
+    pub fn step(&mut self) {
+        let mut gradient = vec![[0.0; 2]; self.params.len()];
+        for entry in &self.entries {
+            let expected = sigmoid(self.scale, self.evaluate(entry));
+            let result = entry.result as f64;
+            // Derivative of the squared error by the evaluation.
+            let slope = 2.0 * (expected - result) * self.sigmoid_slope(expected);
+            let mg_weight = entry.mg_weight as f64;
+            for &(index, coefficient) in &entry.coefficients {
+                let gradient = &mut gradient[index as usize];
+                gradient[0] += slope * coefficient as f64 * mg_weight;
+                gradient[1] += slope * coefficient as f64 * (1.0 - mg_weight);
+            }
+        }
+
+        self.steps += 1;
+        let count = self.entries.len().max(1) as f64;
+        let params = self.params.iter_mut().zip(&gradient);
+        let moments = self.momentum.iter_mut().zip(&mut self.velocity);
+        for ((param, gradient), (momentum, velocity)) in params.zip(moments) {
+            for phase in 0..2 {
+                let gradient = gradient[phase] / count;
+                momentum[phase] = BETA1 * momentum[phase] + (1.0 - BETA1) * gradient;
+                velocity[phase] = BETA2 * velocity[phase] + (1.0 - BETA2) * gradient * gradient;
+                let momentum = momentum[phase] / (1.0 - BETA1.powi(self.steps));
+                let velocity = velocity[phase] / (1.0 - BETA2.powi(self.steps));
+                param[phase] -= self.learning_rate * momentum / (velocity.sqrt() + EPSILON);
+            }
+        }
+    }
+    /// Returns the current parameters rounded to the centi-pawns.
+    pub fn params(&self) -> Params<TaperedScore> {
+        let mut params = self.params.iter();
+        Params::from_fn(|| {
+            let [mg, eg] = params.next().unwrap();
+            TaperedScore::new(mg.round() as i32, eg.round() as i32)
+        })
+    }
+    /// Returns the tapered evaluation of the entry from the white player's point of view.
+    fn evaluate(&self, entry: &Entry) -> f64 {
+        let (mut mg, mut eg) = (0.0, 0.0);
+        for &(index, coefficient) in &entry.coefficients {
+            let [param_mg, param_eg] = self.params[index as usize];
+            mg += coefficient as f64 * param_mg;
+            eg += coefficient as f64 * param_eg;
+        }
+        let mg_weight = entry.mg_weight as f64;
+        mg * mg_weight + eg * (1.0 - mg_weight)
+    }
+    /// Returns the derivative of the sigmoid by the evaluation
+    /// given the value of the sigmoid.
+    fn sigmoid_slope(&self, sigmoid: f64) -> f64 {
+        self.scale * 10f64.ln() / 400.0 * sigmoid * (1.0 - sigmoid)
+    }
+    /// Returns the mean squared error with the scaling constant.
+    fn error_with_scale(&self, scale: f64) -> f64 {
+        let total: f64 = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let error = entry.result as f64 - sigmoid(scale, self.evaluate(entry));
+                error * error
+            })
+            .sum();
+        total / self.entries.len().max(1) as f64
+    }
+}
+
+/// Maps the evaluation in centi-pawns to the expected game result.
+fn sigmoid(scale: f64, eval: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-scale * eval / 400.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use grob_core::{Game, eval::PARAMS};
+
+    use super::*;
+
+    fn positions() -> Vec<LabeledPosition> {
+        [
+            ("4k3/8/8/8/8/8/3P4/3QK3 w - - 0 1", 1.0),
+            ("3qk3/3p4/8/8/8/8/8/4K3 w - - 0 1", 0.0),
+            ("4k3/8/8/8/8/8/3P4/4K3 w - - 0 1", 1.0),
+            ("4k3/3p4/8/8/8/8/8/4K3 b - - 0 1", 0.5),
+            ("4k3/3p4/8/8/8/8/3P4/4K3 b - - 0 1", 0.5),
+        ]
+        .into_iter()
+        .map(|(fen, result)| LabeledPosition {
+            game: Game::try_from_fen(fen).unwrap(),
+            result,
+        })
+        .collect()
+    }
+
+    #[test]
+    fn test_keeps_params() {
+        let tuner = Tuner::new(&positions(), &PARAMS, 1.0);
+        assert_eq!(tuner.params(), PARAMS);
+    }
+
+    #[test]
+    fn test_reduces_error() {
+        let mut tuner = Tuner::new(&positions(), &PARAMS, 1.0);
+        let scale = tuner.fit_scale();
+        assert!(scale > MIN_SCALE && scale < MAX_SCALE, "{scale}");
+        let error = tuner.error();
+        for _ in 0..10 {
+            tuner.step();
+        }
+        assert!(tuner.error() < error, "{} >= {error}", tuner.error());
+    }
+}
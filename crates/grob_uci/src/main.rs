@@ -1,14 +1,43 @@
 //! Engine
 //!
 //! This crate is the driver code for the engine.
+//!
+//! Usage: `grob_uci [--book <path to a Polyglot .bin book>]`
+
+use std::{env, fs};
+
+use grob_core::book::Book;
 
 use crate::server::Server;
 
+mod options;
 mod search;
 mod server;
+mod time;
 mod uci;
 mod uci_cursor;
 
 fn main() {
-    Server::new().run();
+    let mut book = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--book" => book = args.next().and_then(|path| load_book(&path)),
+            _ => eprintln!("Unknown argument: {arg}"),
+        }
+    }
+    Server::new(book).run();
+}
+
+fn load_book(path: &str) -> Option<Book> {
+    let book = fs::read(path)
+        .map_err(|e| e.to_string())
+        .and_then(|bytes| Book::from_bytes(&bytes).map_err(|e| e.to_string()));
+    match book {
+        Ok(book) => Some(book),
+        Err(e) => {
+            eprintln!("Failed to load the book from {path}: {e}");
+            None
+        }
+    }
 }
@@ -0,0 +1,219 @@
+//! Engine options that can be changed with the "setoption" UCI command.
+
+#[cfg(feature = "nnue")]
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Limits of an integer ("spin") option.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Spin {
+    /// Value used until the option is changed.
+    pub default: i64,
+    /// Smallest allowed value.
+    pub min: i64,
+    /// Largest allowed value.
+    pub max: i64,
+}
+
+impl Spin {
+    /// Returns the "option" line of the UCI handshake.
+    pub fn declare(self, name: &str) -> String {
+        format!(
+            "option name {name} type spin default {} min {} max {}",
+            self.default, self.min, self.max
+        )
+    }
+    /// Clamps the value into the allowed range.
+    pub fn clamp(self, value: i64) -> i64 {
+        value.clamp(self.min, self.max)
+    }
+}
+
+/// Transposition table size in mebibytes (MiB).
+pub const HASH: Spin = Spin {
+    default: 16,
+    min: 1,
+    max: 65536,
+};
+
+/// Number of search threads.
+pub const THREADS: Spin = Spin {
+    default: 1,
+    min: 1,
+    max: 256,
+};
+
+/// Number of best lines to report.
+pub const MULTI_PV: Spin = Spin {
+    default: 1,
+    min: 1,
+    max: 256,
+};
+
+/// Time reserved for the communication delays with the GUI (milliseconds).
+pub const MOVE_OVERHEAD: Spin = Spin {
+    default: 10,
+    min: 0,
+    max: 5000,
+};
+
+/// How much the engine wants to avoid draws (centi-pawns).
+pub const CONTEMPT: Spin = Spin {
+    default: 0,
+    min: -100,
+    max: 100,
+};
+
+/// Returns all "option" lines of the UCI handshake.
+pub fn declarations() -> Vec<String> {
+    vec![
+        HASH.declare("Hash"),
+        THREADS.declare("Threads"),
+        String::from("option name Clear Hash type button"),
+        MULTI_PV.declare("MultiPV"),
+        MOVE_OVERHEAD.declare("Move Overhead"),
+        CONTEMPT.declare("Contempt"),
+        #[cfg(feature = "nnue")]
+        String::from("option name EvalFile type string default <empty>"),
+        // Tells the GUI that the engine supports pondering,
+        // the value itself does not change anything.
+        String::from("option name Ponder type check default false"),
+    ]
+}
+
+/// A change of the engine options requested with the "setoption" UCI command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SetOption {
+    /// Resize the transposition table (MiB).
+    Hash(usize),
+    /// Change the number of search threads.
+    Threads(usize),
+    /// Clear the transposition table.
+    ClearHash,
+    /// Change the number of best lines to report.
+    MultiPv(usize),
+    /// Change the time reserved for the communication delays.
+    MoveOverhead(Duration),
+    /// Change how much the engine wants to avoid draws.
+    Contempt(i32),
+    /// Allow the GUI to start the searches in the ponder mode.
+    ///
+    /// The engine can always ponder, so this changes nothing.
+    Ponder(bool),
+    /// Load the NNUE network from the file, or stop using it if `None`.
+    #[cfg(feature = "nnue")]
+    EvalFile(Option<PathBuf>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::uci::Command;
+
+    fn parse(line: &str) -> Option<SetOption> {
+        match line.parse::<Command>() {
+            Ok(Command::SetOption(option)) => Some(option),
+            Ok(command) => panic!("unexpected command: {command:?}"),
+            Err(()) => None,
+        }
+    }
+
+    #[test]
+    fn test_spin_options() {
+        assert_eq!(
+            parse("setoption name Hash value 128"),
+            Some(SetOption::Hash(128))
+        );
+        assert_eq!(
+            parse("setoption name Threads value 4"),
+            Some(SetOption::Threads(4))
+        );
+        assert_eq!(
+            parse("setoption name MultiPV value 3"),
+            Some(SetOption::MultiPv(3))
+        );
+        assert_eq!(
+            parse("setoption name Contempt value -25"),
+            Some(SetOption::Contempt(-25))
+        );
+        assert_eq!(
+            parse("setoption name Move Overhead value 30"),
+            Some(SetOption::MoveOverhead(Duration::from_millis(30)))
+        );
+    }
+
+    #[test]
+    fn test_spin_values_are_clamped() {
+        assert_eq!(
+            parse("setoption name Hash value 0"),
+            Some(SetOption::Hash(HASH.min as usize))
+        );
+        assert_eq!(
+            parse("setoption name Threads value 100000"),
+            Some(SetOption::Threads(THREADS.max as usize))
+        );
+        assert_eq!(
+            parse("setoption name MultiPV value -1"),
+            Some(SetOption::MultiPv(MULTI_PV.min as usize))
+        );
+        assert_eq!(
+            parse("setoption name Contempt value 1000"),
+            Some(SetOption::Contempt(CONTEMPT.max as i32))
+        );
+    }
+
+    #[test]
+    fn test_names_are_case_and_space_insensitive() {
+        assert_eq!(
+            parse("setoption name hash value 64"),
+            Some(SetOption::Hash(64))
+        );
+        assert_eq!(
+            parse("setoption name  Clear   Hash"),
+            Some(SetOption::ClearHash)
+        );
+        assert_eq!(
+            parse("setoption name MULTIPV value 2"),
+            Some(SetOption::MultiPv(2))
+        );
+    }
+
+    #[test]
+    fn test_ponder() {
+        assert_eq!(
+            parse("setoption name Ponder value true"),
+            Some(SetOption::Ponder(true))
+        );
+        assert_eq!(
+            parse("setoption name Ponder value false"),
+            Some(SetOption::Ponder(false))
+        );
+        assert_eq!(parse("setoption name Ponder value maybe"), None);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(parse("setoption name Hash value lots"), None);
+        assert_eq!(parse("setoption name Threads"), None);
+        assert_eq!(parse("setoption name Style value risky"), None);
+        assert_eq!(parse("setoption Hash value 64"), None);
+    }
+
+    #[test]
+    fn test_every_declared_option_is_parsed() {
+        for declaration in declarations() {
+            let (name, rest) = declaration
+                .strip_prefix("option name ")
+                .and_then(|s| s.split_once(" type "))
+                .unwrap();
+            let value = match rest.split_whitespace().next().unwrap() {
+                "spin" => "value 1",
+                "check" => "value true",
+                "string" => "value <empty>",
+                _ => "",
+            };
+            let line = format!("setoption name {name} {value}");
+            assert!(parse(&line).is_some(), "{line}");
+        }
+    }
+}
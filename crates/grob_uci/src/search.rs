@@ -1,18 +1,37 @@
 use std::{
+    cmp::Reverse,
+    fmt::Write,
+    hash::{BuildHasher, RandomState},
     thread,
     time::{Duration, Instant},
 };
 
 use crossbeam::{
-    channel::{Receiver, RecvError, SendError, Sender, unbounded},
+    channel::{Receiver, RecvError, SendError, Sender, at, never, tick, unbounded},
     select,
 };
+#[cfg(feature = "nnue")]
+use grob_core::eval::nnue::NnueEvaluator;
 use grob_core::{
-    ChessMove, Color, Game, LanMove, MoveOrdering, Score, SearchRequest, ServerCommand,
-    ServerResponse, spawn_search_server,
+    ChessMove, Game, LanMove, MoveOrdering, Pv, Score, SearchRequest, SearchResponse,
+    ServerCommand, ServerResponse, Window, book::Book, spawn_search_server,
 };
 
-use crate::uci::Go;
+use crate::{
+    options::{self, SetOption},
+    time::TimeManager,
+    uci::Go,
+};
+
+/// Iterative deepening depth (as [`SearchProgress::running_depth`])
+/// starting from which the aspiration windows are used.
+const ASPIRATION_DEPTH: u64 = 4;
+/// Initial distance in centi-pawns between the aspiration window
+/// and the previous iteration's scores.
+const ASPIRATION_DELTA: i32 = 25;
+/// Distance in centi-pawns past which the aspiration window
+/// is opened completely on the failing side.
+const ASPIRATION_MAX_DELTA: i32 = 1000;
 
 #[derive(Debug, Clone, Copy)]
 pub struct SearchResult {
@@ -26,12 +45,13 @@ pub enum SearchCommand {
     Stop,
     PonderHit,
     UciNewGame,
+    SetOption(SetOption),
 }
 
-pub fn spawn_uci_server() -> (Sender<SearchCommand>, Receiver<SearchResult>) {
+pub fn spawn_uci_server(book: Option<Book>) -> (Sender<SearchCommand>, Receiver<SearchResult>) {
     let (search_send, r) = unbounded();
     let (s, search_recv) = unbounded();
-    thread::spawn(move || UciServer::new(search_send, search_recv).run());
+    thread::spawn(move || UciServer::new(search_send, search_recv, book).run());
     (s, r)
 }
 
@@ -42,6 +62,25 @@ struct UciServer {
     search_send: Sender<SearchResult>,
     search_recv: Receiver<SearchCommand>,
     progress: Option<SearchProgress>,
+    /// Opening book consulted before searching.
+    book: Option<Book>,
+    /// Periodically signals to report the progress of the search.
+    ticker: Receiver<Instant>,
+    /// Signals that the search has reached its deadline.
+    ///
+    /// The iteration running during `ponderhit` was started without
+    /// a deadline, so it has to be canceled from here.
+    alarm: Receiver<Instant>,
+    /// The last known [`grob_core::HashStats::hashfull`].
+    hashfull: u16,
+    /// Number of the best lines to report.
+    multi_pv: usize,
+    /// Time reserved for the communication delays with the GUI.
+    move_overhead: Duration,
+    /// How much the engine wants to avoid draws (centi-pawns).
+    contempt: i32,
+    /// Source of the current time for the time management.
+    clock: fn() -> Instant,
 }
 
 /// Limits of the search.
@@ -53,8 +92,8 @@ struct SearchLimits {
     nodes: Option<u64>,
     /// Stop search if mate in less than this many turns is found.
     mate: Option<u64>,
-    /// Stop search when reaching the deadline.
-    deadline: Option<Instant>,
+    /// Stop search when running out of time.
+    time: Option<TimeManager>,
 }
 
 /// State of the ongoing search.
@@ -64,7 +103,15 @@ struct SearchProgress {
     /// or a suggested ponder move).
     game: Game,
     /// Sorted vector of the searched moves.
-    moves: Vec<(ChessMove, Option<grob_core::SearchResult>)>,
+    moves: Vec<RootMove>,
+    /// Indices into [`SearchProgress::moves`] of the moves in the running batch.
+    batch: Vec<usize>,
+    /// [Aspiration window] of the running batch from the root player's point of view.
+    ///
+    /// [Aspiration window]: https://www.chessprogramming.org/Aspiration_Windows
+    window: Window,
+    /// Distance in centi-pawns of the next widening of the aspiration window.
+    delta: i32,
     /// Limits of the search.
     limits: SearchLimits,
     /// Current iterative deepening depth.
@@ -74,6 +121,97 @@ struct SearchProgress {
     pending_count: usize,
     /// Whether the search is currently running in pondering mode.
     is_pondering: bool,
+    /// Time limits that take effect once the pondering ends.
+    ponder_time: Option<TimeManager>,
+    /// When the search has started.
+    start: Instant,
+    /// Number of nodes searched so far across all iterations.
+    nodes: u64,
+    /// Whether any result of the current iteration was canceled
+    /// or the search was stopped.
+    is_canceled: bool,
+}
+
+/// A root move and the result of its latest search.
+#[derive(Debug, Clone, Copy)]
+struct RootMove {
+    /// The root move.
+    chess_move: ChessMove,
+    /// Result of the latest search, canceled results only replace the missing ones.
+    result: Option<grob_core::SearchResult>,
+    /// Window the result was searched with from the root player's point of view.
+    window: Window,
+}
+
+impl RootMove {
+    fn new(chess_move: ChessMove) -> Self {
+        Self {
+            chess_move,
+            result: None,
+            window: Window::FULL,
+        }
+    }
+    /// Returns the score of the move from the root player's point of view.
+    fn score(&self) -> Option<Score> {
+        self.result.map(|result| result.score.prev())
+    }
+    /// Returns whether the score is exact rather than a bound.
+    fn is_exact(&self) -> bool {
+        self.score()
+            .is_some_and(|score| self.window.is_exact(score))
+    }
+    /// Returns the bound the reported score is, or `None` if it is exact.
+    ///
+    /// A canceled search has not seen all the replies to the move yet,
+    /// so its score can only get worse for the root player.
+    fn bound(&self) -> Option<ScoreBound> {
+        let result = self.result?;
+        let score = result.score.prev();
+        match self.window.is_exact(score) {
+            _ if result.is_canceled => Some(ScoreBound::Upper),
+            true => None,
+            false if score <= self.window.alpha => Some(ScoreBound::Upper),
+            false => Some(ScoreBound::Lower),
+        }
+    }
+}
+
+/// Kind of the bound that a reported score is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ScoreBound {
+    /// The real score is at least the reported one.
+    Lower,
+    /// The real score is at most the reported one.
+    Upper,
+}
+
+/// A line of play starting with one of the root moves.
+#[derive(Debug, Clone, Copy)]
+struct RootLine {
+    /// The root move.
+    root_move: ChessMove,
+    /// Score of the root move.
+    score: Score,
+    /// Bound the score is, or `None` if it is exact.
+    bound: Option<ScoreBound>,
+    /// Expected line of play after the root move.
+    pv: Pv,
+}
+
+/// Aggregated results of a single iterative deepening iteration.
+#[derive(Debug, Clone)]
+struct IterationSummary {
+    /// The best [`UciServer::multi_pv`] lines, starting with the best one.
+    lines: Vec<RootLine>,
+    /// Length of the longest searched line in plies.
+    seldepth: u64,
+}
+
+impl IterationSummary {
+    /// Returns the best line.
+    fn best(&self) -> &RootLine {
+        &self.lines[0]
+    }
 }
 
 /// Simple utility type to improve control flow.
@@ -83,16 +221,34 @@ struct ShouldQuit;
 type Result = std::result::Result<(), ShouldQuit>;
 
 impl UciServer {
-    pub fn new(search_send: Sender<SearchResult>, search_recv: Receiver<SearchCommand>) -> Self {
-        let worker_count = 1;
-        let tt_max_capacity_mib = 16;
-        let (server_send, server_recv) = spawn_search_server(worker_count, tt_max_capacity_mib);
+    pub fn new(
+        search_send: Sender<SearchResult>,
+        search_recv: Receiver<SearchCommand>,
+        book: Option<Book>,
+    ) -> Self {
+        let (server_send, server_recv) = spawn_search_server(
+            options::THREADS.default as usize,
+            options::HASH.default as usize,
+        );
+        // Positions without a network are still evaluated by the tapered evaluator.
+        #[cfg(feature = "nnue")]
+        server_send
+            .send(ServerCommand::SetEvaluator(NnueEvaluator::boxed))
+            .unwrap();
         Self {
             server_send,
             server_recv,
             search_send,
             search_recv,
             progress: None,
+            book,
+            ticker: tick(Duration::from_secs(1)),
+            alarm: never(),
+            hashfull: 0,
+            multi_pv: options::MULTI_PV.default as usize,
+            move_overhead: Duration::from_millis(options::MOVE_OVERHEAD.default as u64),
+            contempt: options::CONTEMPT.default as i32,
+            clock: Instant::now,
         }
     }
     pub fn run(&mut self) {
@@ -108,7 +264,16 @@ impl UciServer {
                 let command = result.map_err(|RecvError| ShouldQuit)?;
                 self.handle_command(command)
             }
-            recv(&self.server_recv) -> result => self.update(result.unwrap()),
+            recv(&self.server_recv) -> result => self.handle_response(result.unwrap()),
+            recv(&self.ticker) -> _ => {
+                self.report_progress();
+                Ok(())
+            }
+            recv(&self.alarm) -> _ => {
+                self.alarm = never();
+                self.cancel();
+                Ok(())
+            }
         }
     }
     fn is_holding(&self) -> bool {
@@ -120,11 +285,24 @@ impl UciServer {
             SearchCommand::Stop => self.stop(),
             SearchCommand::PonderHit => self.ponderhit(),
             SearchCommand::UciNewGame => self.ucinewgame(),
+            SearchCommand::SetOption(option) => self.set_option(option),
         }
     }
     fn go(&mut self, go: Go, mut game: Game) -> Result {
         assert!(self.progress.is_none());
 
+        if let Some(book_move) = self.book_move(&go, &game) {
+            let result = SearchResult {
+                best_move: Some(book_move.lan()),
+                ponder: None,
+            };
+            return self
+                .search_send
+                .send(result)
+                .map_err(|SendError(_)| ShouldQuit);
+        }
+
+        let time = TimeManager::new(&go, game.turn(), self.move_overhead, (self.clock)());
         let moves = go
             .searchmoves
             .map(|moves| {
@@ -134,7 +312,7 @@ impl UciServer {
                     let Some(chess_move) = game.lan_move(lan_move) else {
                         continue;
                     };
-                    vec.push((chess_move, None));
+                    vec.push(RootMove::new(chess_move));
                 }
                 vec
             })
@@ -143,60 +321,81 @@ impl UciServer {
                 let mut vec = Vec::new();
                 game.walk()
                     .for_each_legal_child_node(MoveOrdering::MvvLva, |_, chess_move| {
-                        vec.push((chess_move, None));
+                        vec.push(RootMove::new(chess_move));
                     });
                 vec
             });
-        let deadline = go
-            .movetime
-            .or_else(|| {
-                let turn = game.turn();
-                let inc = match turn {
-                    Color::White => go.winc,
-                    Color::Black => go.binc,
-                }
-                .unwrap_or(Duration::ZERO);
-                match turn {
-                    Color::White => go.wtime,
-                    Color::Black => go.btime,
-                }
-                .map(|time| time / 20 + inc / 2)
-            })
-            .map(|d| Instant::now() + d);
-        _ = go.movestogo;
         let nodes_max = go.nodes;
         let depth_max = go.depth.map(|d| d - 1).filter(|_| !go.infinite);
         let mate = go.mate;
         let ponder = go.ponder;
+        // Opponent's clock is running while pondering, so the own
+        // clock only starts ticking after `ponderhit`.
+        let (time, ponder_time) = match ponder {
+            true => (None, time),
+            false => (time, None),
+        };
+        self.alarm = never();
         self.progress = Some(SearchProgress {
             game,
+            batch: (0..moves.len()).collect(),
             moves,
+            window: Window::FULL,
+            delta: ASPIRATION_DELTA,
             limits: SearchLimits {
                 depth: depth_max,
                 nodes: nodes_max,
                 mate,
-                deadline,
+                time,
             },
             is_pondering: ponder,
+            ponder_time,
             pending_count: 0,
             running_depth: 0,
+            start: Instant::now(),
+            nodes: 0,
+            is_canceled: false,
         });
 
-        if self.progress_mut().game.walk().check_ending().is_right() {
-            return Ok(());
+        // The game might also be over by a draw rule, but it is
+        // up to the GUI to claim it, so the search goes on.
+        if self.progress().moves.is_empty() {
+            return self.check(false);
         }
 
+        let contempt = ServerCommand::SetContempt {
+            contempt: self.contempt,
+            side: self.progress().game.turn(),
+        };
+        self.server_send.send(contempt).unwrap();
+        self.server_send.send(ServerCommand::NewSearch).unwrap();
         self.prepare();
         Ok(())
     }
+    /// Picks a move from the opening book, unless the search
+    /// is not supposed to finish on its own.
+    fn book_move(&self, go: &Go, game: &Game) -> Option<ChessMove> {
+        if go.ponder || go.infinite || go.searchmoves.is_some() {
+            return None;
+        }
+        let random = RandomState::new().hash_one(game.zobrist());
+        self.book.as_ref()?.pick_move(game, random)
+    }
     fn stop(&mut self) -> Result {
         if self.progress.is_none() {
             return Ok(());
         }
 
         self.server_send.send(ServerCommand::Cancel).unwrap();
-        while self.progress().pending_count != 0 {
-            self.update(self.server_recv.recv().unwrap())?;
+        // The server has nothing to cancel if it has already finished
+        // the batch, so the pending results must not start another one.
+        self.progress_mut().is_canceled = true;
+        while self.progress.as_ref().is_some_and(|p| p.pending_count != 0) {
+            self.handle_response(self.server_recv.recv().unwrap())?;
+        }
+        // The last canceled result might have finished the search already.
+        if self.progress.is_none() {
+            return Ok(());
         }
 
         self.check(true)
@@ -206,46 +405,104 @@ impl UciServer {
             return Ok(());
         }
 
-        self.progress_mut().is_pondering = false;
+        let now = (self.clock)();
+        let progress = self.progress_mut();
+        progress.is_pondering = false;
+        if let Some(mut time) = progress.ponder_time.take() {
+            time.restart(now);
+            progress.limits.time = Some(time);
+            self.alarm = at(time.deadline());
+        }
         if self.is_holding() {
             self.check(false)?;
         }
 
         Ok(())
     }
+    /// Cancels the running iteration, the search will stop
+    /// once the canceled results are received.
+    fn cancel(&self) {
+        if self.progress.is_none() || self.is_holding() {
+            return;
+        }
+        self.server_send.send(ServerCommand::Cancel).unwrap();
+    }
     fn ucinewgame(&mut self) -> Result {
         self.server_send.send(ServerCommand::ClearHash).unwrap();
         Ok(())
     }
-    fn update(&mut self, rsp: ServerResponse) -> Result {
+    fn set_option(&mut self, option: SetOption) -> Result {
+        let command = match option {
+            SetOption::Hash(max_mib) => ServerCommand::SetHashSize { max_mib },
+            SetOption::Threads(worker_count) => ServerCommand::SetWorkerCount(worker_count),
+            SetOption::ClearHash => ServerCommand::ClearHash,
+            SetOption::MultiPv(multi_pv) => {
+                self.multi_pv = multi_pv;
+                return Ok(());
+            }
+            SetOption::MoveOverhead(move_overhead) => {
+                self.move_overhead = move_overhead;
+                return Ok(());
+            }
+            SetOption::Contempt(contempt) => {
+                self.contempt = contempt;
+                return Ok(());
+            }
+            SetOption::Ponder(_) => return Ok(()),
+            // The network is attached to the searched positions by the server.
+            #[cfg(feature = "nnue")]
+            SetOption::EvalFile(_) => return Ok(()),
+        };
+        self.server_send.send(command).unwrap();
+        Ok(())
+    }
+    fn handle_response(&mut self, rsp: ServerResponse) -> Result {
+        match rsp {
+            ServerResponse::Search(rsp) => self.update(rsp),
+            ServerResponse::HashStats(stats) => {
+                self.hashfull = stats.hashfull;
+                Ok(())
+            }
+        }
+    }
+    fn update(&mut self, rsp: SearchResponse) -> Result {
         assert!(self.progress().pending_count > 0);
-        let result = self
-            .progress_mut()
-            .moves
-            .iter_mut()
-            .map(|(_, value)| value)
-            .nth(rsp.batch_index)
-            .unwrap()
-            .get_or_insert(rsp.result);
-        if !rsp.result.is_canceled {
-            *result = rsp.result;
+        let progress = self.progress_mut();
+        let window = progress.window;
+        let root_move = &mut progress.moves[progress.batch[rsp.batch_index]];
+        if root_move.result.is_none() || !rsp.result.is_canceled {
+            root_move.result = Some(rsp.result);
+            root_move.window = window;
         }
         self.progress_mut().pending_count -= 1;
+        self.progress_mut().nodes += rsp.result.nodes;
+        // Canceled results don't replace the ones from the previous
+        // iteration, so the cancellation has to be remembered separately.
+        self.progress_mut().is_canceled |= rsp.result.is_canceled;
         self.check(false)
     }
     fn check(&mut self, force: bool) -> Result {
         if self.progress().pending_count != 0 {
             return Ok(());
         }
+        if !force && self.research() {
+            return Ok(());
+        }
 
-        let (best_move, should_stop, should_hold) = self.collect();
+        let (summary, should_stop, should_hold) = self.collect();
+        let is_held = should_stop && should_hold && !force;
+        // Held results are collected again (and reported) later.
+        if let Some(summary) = summary.as_ref().filter(|_| !is_held) {
+            self.report_iteration(summary);
+        }
 
         if should_stop || force {
+            let best = summary.as_ref().map(IterationSummary::best);
             let result = SearchResult {
-                best_move: best_move.map(|m| m.lan()),
-                ponder: None,
+                best_move: best.map(|line| line.root_move.lan()),
+                ponder: best.and_then(|line| line.pv.first()).map(|m| m.lan()),
             };
-            if !should_hold || force {
+            if !is_held {
                 self.progress = None;
                 self.search_send
                     .send(result)
@@ -254,40 +511,151 @@ impl UciServer {
             return Ok(());
         }
 
-        self.progress_mut().running_depth += 1;
+        self.server_send.send(ServerCommand::GetHashStats).unwrap();
+        let progress = self.progress_mut();
+        progress.running_depth += 1;
+        progress.delta = ASPIRATION_DELTA;
+        // The window spans all of the reported lines, so that
+        // only the unexpected changes require a re-search.
+        progress.window = match summary {
+            Some(summary) if progress.running_depth >= ASPIRATION_DEPTH => Window {
+                alpha: aspiration_bound(summary.lines.last().unwrap().score, -ASPIRATION_DELTA),
+                beta: aspiration_bound(summary.best().score, ASPIRATION_DELTA),
+            },
+            _ => Window::FULL,
+        };
+        progress.batch = (0..progress.moves.len()).collect();
         self.prepare();
 
         Ok(())
     }
-    fn collect(&mut self) -> (Option<ChessMove>, bool, bool) {
+    /// Searches again the lines that should be reported, but whose scores
+    /// fell outside of the aspiration window, with a wider window.
+    ///
+    /// Returns whether there are any such lines.
+    fn research(&mut self) -> bool {
+        let multi_pv = self.multi_pv;
+        let progress = self.progress_mut();
+        if progress.is_canceled {
+            return false;
+        }
+
+        let mut order: Vec<_> = (0..progress.moves.len()).collect();
+        order.sort_by_key(|&index| Reverse(progress.moves[index].score()));
+        order.truncate(multi_pv);
+        order.retain(|&index| !progress.moves[index].is_exact());
+        if order.is_empty() {
+            return false;
+        }
+
+        let scores = order
+            .iter()
+            .filter_map(|&index| progress.moves[index].score());
+        let (lowest, highest) = (scores.clone().min(), scores.max());
+        progress.delta = progress.delta.saturating_mul(2);
+        let window = &mut progress.window;
+        // Fail-soft bounds are usually closer to the real score than
+        // the window itself, so the widening starts from them.
+        if let Some(lowest) = lowest.filter(|&score| score <= window.alpha) {
+            window.alpha = aspiration_bound(lowest, -progress.delta);
+        }
+        if let Some(highest) = highest.filter(|&score| score >= window.beta) {
+            window.beta = aspiration_bound(highest, progress.delta);
+        }
+        progress.batch = order;
+        self.prepare();
+        true
+    }
+    /// Prints the results of the current iteration.
+    fn report_iteration(&self, summary: &IterationSummary) {
+        for index in 0..summary.lines.len() {
+            println!("{}", self.info_line(Some((summary, index))));
+        }
+    }
+    /// Periodically prints the statistics of a running search.
+    fn report_progress(&self) {
+        if self.progress.is_none() || self.is_holding() {
+            return;
+        }
+        self.server_send.send(ServerCommand::GetHashStats).unwrap();
+        println!("{}", self.info_line(None));
+    }
+    /// Returns the `info` line with the statistics of the search so far
+    /// and the line with the specified index if the iteration is complete.
+    fn info_line(&self, summary: Option<(&IterationSummary, usize)>) -> String {
+        let progress = self.progress();
+        let mut line = format!("info depth {}", progress.running_depth + 1);
+        if let Some((summary, index)) = summary {
+            write!(line, " seldepth {}", summary.seldepth).unwrap();
+            if self.multi_pv > 1 {
+                write!(line, " multipv {}", index + 1).unwrap();
+            }
+            let root_line = &summary.lines[index];
+            match root_line.score {
+                Score::Cp(cp) => write!(line, " score cp {cp}").unwrap(),
+                Score::Mating(n) => write!(line, " score mate {n}").unwrap(),
+                Score::Mated(n) => write!(line, " score mate -{n}").unwrap(),
+            }
+            match root_line.bound {
+                Some(ScoreBound::Lower) => line.push_str(" lowerbound"),
+                Some(ScoreBound::Upper) => line.push_str(" upperbound"),
+                None => {}
+            }
+        }
+        let time = progress.start.elapsed();
+        let nps = (progress.nodes as f64 / time.as_secs_f64().max(1e-3)) as u64;
+        write!(
+            line,
+            " nodes {} nps {nps} time {} hashfull {}",
+            progress.nodes,
+            time.as_millis(),
+            self.hashfull,
+        )
+        .unwrap();
+        if let Some((summary, index)) = summary {
+            let root_line = &summary.lines[index];
+            write!(line, " pv {}", root_line.root_move.lan()).unwrap();
+            for chess_move in root_line.pv.iter() {
+                write!(line, " {}", chess_move.lan()).unwrap();
+            }
+        }
+        line
+    }
+    /// Returns the summary of the current iteration, whether the search should
+    /// stop and whether the result should be held until `stop` or `ponderhit`.
+    fn collect(&mut self) -> (Option<IterationSummary>, bool, bool) {
         assert!(self.progress().pending_count == 0);
         let progress = self.progress();
-        let mut best_move = None;
-        let mut score = None;
+        let mut lines = Vec::with_capacity(progress.moves.len());
         let mut nodes = 0;
-        let mut unfinished = false;
+        let mut seldepth = 0;
+        let mut unfinished = progress.is_canceled;
 
-        for &(chess_move, result) in progress.moves.iter() {
-            let result = result.unwrap();
-            if score.is_none_or(|s| result.score > s) {
-                score = Some(result.score);
-                best_move = Some(chess_move);
-            }
+        for root_move in progress.moves.iter() {
+            let result = root_move.result.unwrap();
+            lines.push(RootLine {
+                root_move: root_move.chess_move,
+                score: result.score.prev(),
+                bound: root_move.bound(),
+                pv: result.pv,
+            });
             nodes += result.nodes;
+            seldepth = seldepth.max(result.seldepth + 1);
             unfinished |= result.is_canceled;
         }
 
-        if best_move.is_none() {
+        if lines.is_empty() {
             return (None, true, progress.is_pondering);
         }
 
-        let best_move = best_move.unwrap();
-        let score = score.unwrap();
+        // The reported lines are re-searched until their scores are exact,
+        // the scores of the other moves are upper bounds below them.
+        // The sort is stable, so the ties are resolved in favour of
+        // the move that comes first.
+        lines.sort_by_key(|line| Reverse(line.score));
+        lines.truncate(self.multi_pv);
+        let score = lines[0].score;
 
-        let time_fails = progress
-            .limits
-            .deadline
-            .is_some_and(|d| Instant::now() >= d);
         let depth_fails = progress
             .limits
             .depth
@@ -298,25 +666,39 @@ impl UciServer {
             .mate
             .is_some_and(|n| score >= Score::Mating(n) || score <= Score::Mated(n));
         let depth_limited = progress.running_depth == u8::MAX as u64;
+        let is_pondering = progress.is_pondering;
+        let best_move = lines[0].root_move;
+        let now = (self.clock)();
+        let time_fails = self
+            .progress_mut()
+            .limits
+            .time
+            .as_mut()
+            .is_some_and(|time| {
+                time.update(best_move, score);
+                time.should_stop(now)
+            });
         let should_stop =
             unfinished | time_fails | depth_fails | nodes_fail | mate_fail | depth_limited;
-        let should_hold = should_stop & (progress.is_pondering | depth_limited);
+        let should_hold = should_stop & (is_pondering | depth_limited);
 
-        (Some(best_move), should_stop, should_hold)
+        let summary = IterationSummary { lines, seldepth };
+        (Some(summary), should_stop, should_hold)
     }
     fn prepare(&mut self) {
         assert!(self.progress().pending_count == 0);
         let progress = self.progress.as_mut().unwrap();
-        progress.pending_count = progress.moves.len();
+        progress.pending_count = progress.batch.len();
         let mut vec = Vec::with_capacity(progress.pending_count);
-        for &(chess_move, _) in &progress.moves {
+        for &index in &progress.batch {
             let mut game = progress.game.clone();
-            game.make_move(chess_move);
+            game.make_move(progress.moves[index].chess_move);
             vec.push(SearchRequest {
                 game,
                 depth: progress.running_depth,
                 nodes: progress.limits.nodes,
-                deadline: progress.limits.deadline,
+                deadline: progress.limits.time.map(|time| time.deadline()),
+                window: progress.window.next(),
             });
         }
         self.server_send
@@ -331,6 +713,17 @@ impl UciServer {
     }
 }
 
+/// Returns the bound of the aspiration window `delta` centi-pawns away from
+/// the score, the window is opened completely on that side for mate scores
+/// and for distances of at least [`ASPIRATION_MAX_DELTA`].
+fn aspiration_bound(score: Score, delta: i32) -> Score {
+    match score {
+        Score::Cp(_) if delta.abs() < ASPIRATION_MAX_DELTA => score.add_cp(delta),
+        _ if delta < 0 => Window::FULL.alpha,
+        _ => Window::FULL.beta,
+    }
+}
+
 // #[derive(Debug)]
 // pub struct Search {
 //     server_send: Sender<ServerCommand>,
@@ -595,3 +988,83 @@ impl UciServer {
 //         self.progress.as_mut().unwrap()
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::LazyLock;
+
+    use super::*;
+
+    #[test]
+    fn test_root_move_bound() {
+        let chess_move = Game::initial_position()
+            .walk()
+            .check_ending()
+            .left()
+            .unwrap();
+        let root_move = |score, window, is_canceled| RootMove {
+            chess_move,
+            result: Some(grob_core::SearchResult {
+                score: Score::Cp(score).next(),
+                nodes: 1,
+                seldepth: 0,
+                best_move: None,
+                pv: Pv::EMPTY,
+                is_canceled,
+            }),
+            window,
+        };
+        let window = Window {
+            alpha: Score::Cp(0),
+            beta: Score::Cp(50),
+        };
+        assert_eq!(root_move(10, window, false).bound(), None);
+        assert_eq!(root_move(0, window, false).bound(), Some(ScoreBound::Upper));
+        assert_eq!(
+            root_move(60, window, false).bound(),
+            Some(ScoreBound::Lower)
+        );
+        assert_eq!(root_move(10, window, true).bound(), Some(ScoreBound::Upper));
+        assert_eq!(root_move(900, Window::FULL, false).bound(), None);
+        assert_eq!(RootMove::new(chess_move).bound(), None);
+    }
+
+    #[test]
+    fn test_clock_starts_at_ponderhit() {
+        static PONDERHIT: LazyLock<Instant> =
+            LazyLock::new(|| Instant::now() + Duration::from_secs(60));
+        let (search_send, search_recv) = unbounded();
+        let (_command_send, command_recv) = unbounded();
+        let mut server = UciServer::new(search_send, command_recv, None);
+        let go = Go {
+            movetime: Some(Duration::from_millis(200)),
+            ponder: true,
+            ..Go::default()
+        };
+        server.go(go, Game::initial_position()).unwrap();
+        // The search keeps pondering past its time limit.
+        assert!(server.progress().limits.time.is_none());
+
+        server.clock = || *PONDERHIT;
+        server.ponderhit().unwrap();
+        let time = server.progress().limits.time.unwrap();
+        let movetime = Duration::from_millis(200) - server.move_overhead;
+        assert_eq!(time.deadline(), *PONDERHIT + movetime);
+        assert!(!time.should_stop(*PONDERHIT));
+
+        server.stop().unwrap();
+        assert!(search_recv.try_recv().unwrap().best_move.is_some());
+    }
+
+    #[test]
+    fn test_go_without_legal_moves() {
+        let (search_send, search_recv) = unbounded();
+        let (_command_send, command_recv) = unbounded();
+        let mut server = UciServer::new(search_send, command_recv, None);
+        let game = Game::try_from_fen("7k/6Q1/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+        server.go(Go::default(), game).unwrap();
+        let result = search_recv.try_recv().unwrap();
+        assert_eq!(result.best_move, None);
+        assert_eq!(result.ponder, None);
+    }
+}
@@ -2,14 +2,22 @@ use crossbeam::{
     channel::{Receiver, Sender},
     select,
 };
-use grob_core::Game;
+use grob_core::{Game, book::Book, eval::Evaluator};
 use std::{
     collections::VecDeque,
     fmt::Write,
     io::{self, stdin},
 };
+#[cfg(feature = "nnue")]
+use std::{fs, path::Path, sync::Arc};
+
+#[cfg(not(feature = "nnue"))]
+use grob_core::eval::TaperedEvaluator;
+#[cfg(feature = "nnue")]
+use grob_core::eval::nnue::{Network, NnueEvaluator};
 
 use crate::{
+    options,
     search::{SearchCommand, SearchResult, spawn_uci_server},
     uci::{Command, spawn_uci_parser},
 };
@@ -20,6 +28,11 @@ const AUTHOR_NAME: &str = "Ivan Sigaev";
 #[derive(Debug)]
 pub struct Server {
     game: Game,
+    /// Evaluator for the "eval" command.
+    evaluator: Box<dyn Evaluator>,
+    /// NNUE network loaded with the "EvalFile" option.
+    #[cfg(feature = "nnue")]
+    network: Option<Arc<Network>>,
     command_recv: Receiver<io::Result<Command>>,
     pending_commands: VecDeque<Command>,
     search_send: Sender<SearchCommand>,
@@ -29,12 +42,18 @@ pub struct Server {
 }
 
 impl Server {
-    pub fn new() -> Self {
+    pub fn new(book: Option<Book>) -> Self {
         let command_recv = spawn_uci_parser(Box::new(stdin()));
-        let (search_send, search_recv) = spawn_uci_server();
+        let (search_send, search_recv) = spawn_uci_server(book);
         let game = Game::initial_position();
         Self {
             game,
+            #[cfg(not(feature = "nnue"))]
+            evaluator: TaperedEvaluator::boxed(),
+            #[cfg(feature = "nnue")]
+            evaluator: NnueEvaluator::boxed(),
+            #[cfg(feature = "nnue")]
+            network: None,
             command_recv,
             pending_commands: VecDeque::new(),
             search_send,
@@ -47,35 +66,58 @@ impl Server {
         while !self.should_quit {
             select! {
                 recv(self.command_recv) -> result => {
-                    _ = self.handle_command(result.unwrap().unwrap());
+                    self.handle_command(result.unwrap().unwrap());
                 }
                 recv(self.search_recv) -> result => self.update_search(result.unwrap()),
             }
         }
     }
-    fn handle_command(&mut self, command: Command) -> bool {
-        if !self.pending_commands.is_empty()
-            || (self.expecting_res
-                && matches!(
-                    command,
-                    Command::UciNewGame | Command::Position(_) | Command::Go(_)
-                ))
-        {
+    fn handle_command(&mut self, command: Command) {
+        // Commands that change the state of the engine have to wait
+        // until the search ends, the rest are executed immediately.
+        let should_wait = matches!(
+            command,
+            Command::UciNewGame
+                | Command::SetOption(_)
+                | Command::Position(_)
+                | Command::Go(_)
+                | Command::Eval
+        );
+        if should_wait && (self.expecting_res || !self.pending_commands.is_empty()) {
             self.pending_commands.push_back(command);
-            return false;
+            return;
         }
 
+        self.execute_command(command);
+    }
+    fn execute_command(&mut self, command: Command) {
         match command {
             Command::Uci => {
                 println!("id name {ENGINE_NAME}");
                 println!("id author {AUTHOR_NAME}");
+                for option in options::declarations() {
+                    println!("{option}");
+                }
                 println!("uciok");
             }
             Command::IsReady => {
                 println!("readyok");
             }
             Command::UciNewGame => self.search_send.send(SearchCommand::UciNewGame).unwrap(),
-            Command::Position(game) => self.game = game,
+            #[cfg(feature = "nnue")]
+            Command::SetOption(options::SetOption::EvalFile(path)) => {
+                self.network = path.and_then(|path| load_network(&path)).map(Arc::new);
+                self.game.set_network(self.network.clone());
+            }
+            Command::SetOption(option) => self
+                .search_send
+                .send(SearchCommand::SetOption(option))
+                .unwrap(),
+            Command::Position(game) => {
+                self.game = game;
+                #[cfg(feature = "nnue")]
+                self.game.set_network(self.network.clone());
+            }
             Command::Go(go) => {
                 self.expecting_res = true;
                 self.search_send
@@ -98,19 +140,19 @@ impl Server {
                 }
                 self.should_quit = true;
             }
+            Command::Eval => println!("{}", self.evaluator.trace(&self.game)),
         }
-
-        true
     }
     fn update_search(&mut self, res: SearchResult) {
         assert!(self.expecting_res);
         Self::display_search_result(res);
         self.expecting_res = false;
 
-        while let Some(command) = self.pending_commands.pop_front() {
-            if !self.handle_command(command) {
+        while !self.expecting_res {
+            let Some(command) = self.pending_commands.pop_front() else {
                 break;
-            }
+            };
+            self.execute_command(command);
         }
     }
     fn stop_search(&mut self) {
@@ -130,3 +172,24 @@ impl Server {
         println!("{msg}");
     }
 }
+
+/// Loads the NNUE network, reporting the errors to the GUI.
+#[cfg(feature = "nnue")]
+fn load_network(path: &Path) -> Option<Network> {
+    let network = fs::read(path)
+        .map_err(|e| e.to_string())
+        .and_then(|bytes| Network::from_bytes(&bytes).map_err(|e| e.to_string()));
+    match network {
+        Ok(network) => {
+            println!("info string Loaded the network from {}", path.display());
+            Some(network)
+        }
+        Err(e) => {
+            println!(
+                "info string Failed to load the network from {}: {e}",
+                path.display()
+            );
+            None
+        }
+    }
+}
@@ -0,0 +1,256 @@
+//! Time management
+//!
+//! Decides for how long to think about a move based on the clock.
+
+use std::time::{Duration, Instant};
+
+use grob_core::{ChessMove, Color, Score};
+
+use crate::uci::Go;
+
+/// Number of moves the remaining time is split between
+/// if the time control does not specify it.
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+/// Largest number of moves the remaining time is split between.
+const MAX_MOVES_TO_GO: u32 = 50;
+/// How many times the hard limit may exceed the planned time.
+const HARD_LIMIT_RATIO: u32 = 4;
+/// Soft limit scale (percent) by the number of iterations
+/// the best move stayed the same.
+const STABILITY_SCALE: [u32; 5] = [140, 115, 100, 85, 75];
+/// Score drop between the iterations (centi-pawns)
+/// that makes the search think longer.
+const SCORE_DROP: i64 = 30;
+/// Score drop between the iterations (centi-pawns)
+/// that makes the search think a lot longer.
+const BIG_SCORE_DROP: i64 = 100;
+
+/// Decides when the search should stop.
+///
+/// The search should not start a new iteration after the soft limit,
+/// which is adjusted after every iteration depending on how stable
+/// its results are. The hard limit is never exceeded.
+#[derive(Debug, Clone, Copy)]
+pub struct TimeManager {
+    /// When the search has started.
+    start: Instant,
+    /// Planned search time.
+    soft: Duration,
+    /// Search must never exceed this time.
+    hard: Duration,
+    /// Whether the soft limit can be adjusted.
+    is_flexible: bool,
+    /// Soft limit scale (percent) based on the last iterations.
+    scale: u32,
+    /// Best move of the last iteration.
+    best_move: Option<ChessMove>,
+    /// Score of the last iteration.
+    score: Option<Score>,
+    /// Number of consecutive iterations that had the same best move.
+    stability: usize,
+}
+
+impl TimeManager {
+    /// Plans the time for the search started at `now`.
+    ///
+    /// Returns `None` if the search is not limited by time.
+    pub fn new(go: &Go, turn: Color, move_overhead: Duration, now: Instant) -> Option<Self> {
+        if go.infinite {
+            return None;
+        }
+
+        if let Some(movetime) = go.movetime {
+            let time = movetime.saturating_sub(move_overhead);
+            return Some(Self::with_limits(time, time, false, now));
+        }
+
+        let (time, inc) = match turn {
+            Color::White => (go.wtime?, go.winc),
+            Color::Black => (go.btime?, go.binc),
+        };
+        let inc = inc.unwrap_or(Duration::ZERO);
+        let available = time.saturating_sub(move_overhead);
+        // With cyclic time controls the clock is refilled after `movestogo`
+        // moves, otherwise the game is expected to last a while longer.
+        let moves_to_go = go.movestogo.map_or(DEFAULT_MOVES_TO_GO, |n| {
+            n.clamp(1, MAX_MOVES_TO_GO as u64) as u32
+        });
+        let planned = available / moves_to_go + inc * 3 / 4;
+        let hard = (planned * HARD_LIMIT_RATIO).min(available * 4 / 5);
+        Some(Self::with_limits(planned.min(hard), hard, true, now))
+    }
+    fn with_limits(soft: Duration, hard: Duration, is_flexible: bool, start: Instant) -> Self {
+        Self {
+            start,
+            soft,
+            hard,
+            is_flexible,
+            scale: 100,
+            best_move: None,
+            score: None,
+            stability: 0,
+        }
+    }
+    /// Starts counting the time from `now`.
+    pub fn restart(&mut self, now: Instant) {
+        self.start = now;
+    }
+    /// Returns the time after which the search must be stopped.
+    pub fn deadline(&self) -> Instant {
+        self.start + self.hard
+    }
+    /// Adjusts the soft limit based on the results of a completed iteration.
+    pub fn update(&mut self, best_move: ChessMove, score: Score) {
+        if self.best_move == Some(best_move) {
+            self.stability = (self.stability + 1).min(STABILITY_SCALE.len() - 1);
+        } else {
+            self.stability = 0;
+        }
+        let drop = self.score.map_or(0, |prev| as_cp(prev) - as_cp(score));
+        let drop_scale = match drop {
+            BIG_SCORE_DROP.. => 200,
+            SCORE_DROP.. => 140,
+            _ => 100,
+        };
+
+        self.scale = STABILITY_SCALE[self.stability] * drop_scale / 100;
+        self.best_move = Some(best_move);
+        self.score = Some(score);
+    }
+    /// Returns `true` if the search should not start another iteration at `now`.
+    pub fn should_stop(&self, now: Instant) -> bool {
+        let soft = match self.is_flexible {
+            true => (self.soft * self.scale / 100).min(self.hard),
+            false => self.soft,
+        };
+        now.saturating_duration_since(self.start) >= soft
+    }
+}
+
+/// Converts a score to centi-pawns, mates are worth more than any material.
+fn as_cp(score: Score) -> i64 {
+    const MATE: i64 = 1_000_000;
+    match score {
+        Score::Cp(cp) => cp as i64,
+        Score::Mating(n) => MATE - n as i64,
+        Score::Mated(n) => n as i64 - MATE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use grob_core::{Game, MoveOrdering};
+
+    use super::*;
+
+    fn clock(time_ms: u64, inc_ms: u64, movestogo: Option<u64>) -> Go {
+        Go {
+            wtime: Some(Duration::from_millis(time_ms)),
+            btime: Some(Duration::from_millis(time_ms)),
+            winc: Some(Duration::from_millis(inc_ms)),
+            binc: Some(Duration::from_millis(inc_ms)),
+            movestogo,
+            ..Go::default()
+        }
+    }
+
+    fn some_moves() -> Vec<ChessMove> {
+        let mut moves = Vec::new();
+        let mut game = Game::initial_position();
+        game.walk()
+            .for_each_legal_child_node(MoveOrdering::MvvLva, |_, chess_move| {
+                moves.push(chess_move)
+            });
+        moves
+    }
+
+    #[test]
+    fn test_unlimited() {
+        let overhead = Duration::from_millis(10);
+        let infinite = Go {
+            infinite: true,
+            ..clock(1000, 0, None)
+        };
+        assert!(TimeManager::new(&infinite, Color::White, overhead, Instant::now()).is_none());
+        let depth = Go {
+            depth: Some(5),
+            ..Go::default()
+        };
+        assert!(TimeManager::new(&depth, Color::White, overhead, Instant::now()).is_none());
+    }
+
+    #[test]
+    fn test_never_exceeds_clock() {
+        let overhead = Duration::from_millis(50);
+        for (time, inc, movestogo) in [
+            (100, 0, None),
+            (1000, 5000, None),
+            (60_000, 1000, Some(1)),
+            (60_000, 0, Some(40)),
+            (30, 0, None),
+        ] {
+            let go = clock(time, inc, movestogo);
+            let tm = TimeManager::new(&go, Color::Black, overhead, Instant::now()).unwrap();
+            let available = Duration::from_millis(time).saturating_sub(overhead);
+            assert!(tm.hard <= available);
+            assert!(tm.soft <= tm.hard);
+        }
+    }
+
+    #[test]
+    fn test_restart() {
+        // Pondering runs past the limits, which only start at `ponderhit`.
+        let go = Go {
+            movetime: Some(Duration::from_millis(100)),
+            ponder: true,
+            ..Go::default()
+        };
+        let start = Instant::now();
+        let mut tm = TimeManager::new(&go, Color::White, Duration::ZERO, start).unwrap();
+        let ponderhit = start + Duration::from_millis(150);
+        assert!(tm.should_stop(ponderhit));
+        assert!(tm.deadline() < ponderhit);
+
+        tm.restart(ponderhit);
+        assert!(!tm.should_stop(ponderhit));
+        assert_eq!(tm.deadline(), ponderhit + tm.hard);
+        assert!(tm.should_stop(ponderhit + Duration::from_millis(100)));
+    }
+
+    #[test]
+    fn test_movestogo() {
+        let overhead = Duration::ZERO;
+        let sudden_death = TimeManager::new(
+            &clock(60_000, 0, None),
+            Color::White,
+            overhead,
+            Instant::now(),
+        );
+        let last_move = TimeManager::new(
+            &clock(60_000, 0, Some(1)),
+            Color::White,
+            overhead,
+            Instant::now(),
+        );
+        assert!(sudden_death.unwrap().soft < last_move.unwrap().soft);
+    }
+
+    #[test]
+    fn test_stability() {
+        let go = clock(60_000, 0, None);
+        let moves = some_moves();
+        let mut stable =
+            TimeManager::new(&go, Color::White, Duration::ZERO, Instant::now()).unwrap();
+        let mut unstable = stable;
+        for i in 0..5 {
+            stable.update(moves[0], Score::Cp(0));
+            unstable.update(moves[i], Score::Cp(0));
+        }
+        assert!(stable.scale < unstable.scale);
+
+        let mut dropping = stable;
+        stable.update(moves[0], Score::Cp(0));
+        dropping.update(moves[0], Score::Cp(-200));
+        assert!(stable.scale < dropping.scale);
+    }
+}
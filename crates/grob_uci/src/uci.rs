@@ -8,7 +8,10 @@ use std::{
     time::Duration,
 };
 
-use crate::uci_cursor::Cursor;
+use crate::{
+    options::{self, SetOption, Spin},
+    uci_cursor::Cursor,
+};
 
 /// Spawns a thread that will parse UCI commands from a given `Read` trait object
 /// and returns a channel from it.
@@ -47,6 +50,8 @@ pub enum Command {
     Uci,
     /// \>\> isready - UCI ping.
     IsReady,
+    /// \>\> setoption name ... [value ...] - change an engine option.
+    SetOption(SetOption),
     /// \>\> ucinewgame - clear hash table.
     UciNewGame,
     /// \>\> position ... - setup position.
@@ -59,6 +64,10 @@ pub enum Command {
     PonderHit,
     /// \>\> quit - terminate the program.
     Quit,
+    /// \>\> eval - print the static evaluation of the position term by term.
+    ///
+    /// This is not a part of the UCI protocol.
+    Eval,
 }
 
 /// Parameters for the "go" UCI command.
@@ -101,12 +110,14 @@ impl FromStr for Command {
         Ok(match token {
             "uci" => Command::Uci,
             "isready" => Command::IsReady,
+            "setoption" => cursor.parse_setoption().ok_or(())?,
             "ucinewgame" => Command::UciNewGame,
             "position" => cursor.parse_position(),
             "go" => cursor.parse_go(),
             "stop" => Command::Stop,
             "ponderhit" => Command::PonderHit,
             "quit" => Command::Quit,
+            "eval" => Command::Eval,
             _ => return Err(()),
         })
     }
@@ -141,6 +152,40 @@ impl Cursor<'_> {
         let game = maybe_game.unwrap_or_else(Game::initial_position);
         Command::Position(game)
     }
+    fn parse_setoption(&mut self) -> Option<Command> {
+        if self.next_token() != Some("name") {
+            return None;
+        }
+        // Option names may contain spaces and are case insensitive.
+        let name = self
+            .until_token("value")
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_ascii_lowercase();
+        let value = self.rest().trim();
+        let spin = |spin: Spin| value.parse().ok().map(|value| spin.clamp(value));
+
+        let option = match name.as_str() {
+            "hash" => SetOption::Hash(spin(options::HASH)? as usize),
+            "threads" => SetOption::Threads(spin(options::THREADS)? as usize),
+            "clear hash" => SetOption::ClearHash,
+            "multipv" => SetOption::MultiPv(spin(options::MULTI_PV)? as usize),
+            "move overhead" => {
+                let millis = spin(options::MOVE_OVERHEAD)? as u64;
+                SetOption::MoveOverhead(Duration::from_millis(millis))
+            }
+            "contempt" => SetOption::Contempt(spin(options::CONTEMPT)? as i32),
+            "ponder" => SetOption::Ponder(value.parse().ok()?),
+            #[cfg(feature = "nnue")]
+            "evalfile" => SetOption::EvalFile(match value {
+                "" | "<empty>" => None,
+                path => Some(path.into()),
+            }),
+            _ => return None,
+        };
+        Some(Command::SetOption(option))
+    }
     fn parse_go(&mut self) -> Command {
         let mut go = Go::default();
         while let Some(token) = self.next_token() {
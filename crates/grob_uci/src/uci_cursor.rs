@@ -17,9 +17,15 @@ impl<'line> Cursor<'line> {
             if self.next_token() == Some(stop) {
                 break;
             }
+            after = self.len();
         }
         &s[..(before - after)]
     }
+    pub fn rest(&mut self) -> &'line str {
+        let s = self.0.as_str();
+        self.0 = "".chars();
+        s
+    }
     pub fn next_token(&mut self) -> Option<&'line str> {
         self.skip_whitespace();
         if self.is_empty() {